@@ -1,229 +1,616 @@
-//! Implementations of different algorithms that operate on shared NumberVecs
+//! Implementations of different algorithms that operate on `trace::Tracer`s
 //!
-//! The algorithms should take locks for as little time as required since
-//! locking on NumberVecs blocks the UI update. An often repeated pattern to that is
+//! Algorithms don't lock or mutate the shared `NumberVec`s displayed in the
+//! UI. They run on the values owned by a `trace::Tracer` and every comparison,
+//! swap and highlight is recorded, so a run can be played back afterwards.
+//! Because nothing is displayed while the algorithm runs, there is no need to
+//! sleep or request repaints here, that's the job of `player::play`.
 //!
-//! ```
-//! let nums = numbers.lock().unwrap();  // lock mutex
-//! let length = nums.values.len();      // save for later use
-//! drop(nums);                          // dropping the reference unlocks the mutex
-//! ```
-//! Each algorithm that's should be watchable should call
-//! `ctx.request_repaint()` and sleep afterwards. That ensures the UI thread can
-//! display the changes. The sleeping duration is controlled by the "animation
-//! speed" slider in the UI.
-use eframe::egui;
-use rand::prelude::*;
-use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
-use std::sync::{Arc, Mutex};
-use std::{thread, time};
-
-use crate::datatypes;
-
-/// Sort `numbers` using bubble sort.
-///
-/// # Arguments
-///
-/// - numbers: `datatypes::NumberVec` to sort
-/// - animation_delay: time to sleep in ms after each comparison
-/// - ctx: egui::Context to request repainting after each comparison
-/// - stop_flag: Set to `true` from another thread to abort
-pub fn bubblesort(
-    numbers: Arc<Mutex<datatypes::NumberVec>>,
-    animation_delay: Arc<AtomicU8>,
-    ctx: &egui::Context,
-    stop_flag: Arc<AtomicBool>,
-) {
-    let nums = numbers.lock().unwrap();
-    let length = nums.values.len();
-    drop(nums);
+//! To make an algorithm selectable in the UI, implement `SortAlgorithm` for it
+//! and add it to `ALGORITHMS`. Algorithms mark the line of their pseudocode
+//! they are at with `Tracer::line` right before each comparison or swap.
+use std::cmp::Ordering;
+
+use crate::trace::Tracer;
+use crate::{datatypes, names};
+
+/// A sorting algorithm that can be selected in the UI
+pub trait SortAlgorithm: Sync {
+    fn name(&self) -> &'static str;
+    /// Short explanation shown to the user
+    fn description(&self) -> &'static str;
+    /// Lines of pseudocode shown next to the bars, indexed by `Tracer::line`
+    fn pseudocode(&self) -> &'static [&'static str];
+    /// Sort all values of `tracer`
+    fn run(&self, tracer: &mut Tracer);
+}
+
+/// All algorithms that can be selected, in the order they are shown
+pub static ALGORITHMS: &[&dyn SortAlgorithm] = &[
+    &BubbleSort,
+    &QuickSort,
+    &InsertionSort,
+    &BinaryInsertionSort,
+    &SelectionSort,
+    &DoubleSelectionSort,
+    &MergeSort,
+    &BottomUpMergeSort,
+];
+
+/// Look up an algorithm in `ALGORITHMS` by name, see `names::normalize`
+pub fn find(name: &str) -> Option<&'static dyn SortAlgorithm> {
+    names::find(ALGORITHMS, name, |a| a.name())
+}
+
+pub struct BubbleSort;
+
+impl SortAlgorithm for BubbleSort {
+    fn name(&self) -> &'static str {
+        "Bubble Sort"
+    }
+    fn description(&self) -> &'static str {
+        "Repeatedly swaps neighbours that are out of order, moving the largest \
+         remaining value to the end with every pass."
+    }
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "for n in 0..len:",
+            "  for i in 0..len - n - 1:",
+            "    if a[i] > a[i + 1]:",
+            "      swap a[i], a[i + 1]",
+        ]
+    }
+    fn run(&self, tracer: &mut Tracer) {
+        bubblesort(tracer);
+    }
+}
+
+pub struct QuickSort;
+
+impl SortAlgorithm for QuickSort {
+    fn name(&self) -> &'static str {
+        "Quick Sort"
+    }
+    fn description(&self) -> &'static str {
+        "Partitions the values around a pivot (the last value of the range), \
+         then sorts both sides recursively."
+    }
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "quicksort(lo, hi):",
+            "  if lo >= hi: return",
+            "  p = partition(lo, hi)",
+            "  quicksort(lo, p - 1)",
+            "  quicksort(p + 1, hi)",
+            "",
+            "partition(lo, hi):",
+            "  pivot = a[hi]",
+            "  i = lo - 1",
+            "  for j in lo..hi:",
+            "    if a[j] <= pivot:",
+            "      i = i + 1",
+            "      swap a[i], a[j]",
+            "  swap a[i + 1], a[hi]",
+            "  return i + 1",
+        ]
+    }
+    fn run(&self, tracer: &mut Tracer) {
+        if tracer.len() > 1 {
+            let highest_index = tracer.len() - 1;
+            quicksort(tracer, 0, highest_index);
+        }
+    }
+}
+
+pub struct InsertionSort;
+
+impl SortAlgorithm for InsertionSort {
+    fn name(&self) -> &'static str {
+        "Insertion Sort"
+    }
+    fn description(&self) -> &'static str {
+        "Takes one value after another as the key and shifts all larger values \
+         right to make room for it, growing a sorted part at the start."
+    }
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "for i in 1..len:",
+            "  key = a[i]",
+            "  j = i",
+            "  while j > 0 and a[j - 1] > key:",
+            "    a[j] = a[j - 1]",
+            "    j = j - 1",
+            "  a[j] = key",
+        ]
+    }
+    fn run(&self, tracer: &mut Tracer) {
+        insertionsort(tracer);
+    }
+}
+
+pub struct BinaryInsertionSort;
+
+impl SortAlgorithm for BinaryInsertionSort {
+    fn name(&self) -> &'static str {
+        "Binary Insertion Sort"
+    }
+    fn description(&self) -> &'static str {
+        "Like insertion sort, but finds the position of the key in the sorted \
+         part with a binary search, then shifts it there without comparing."
+    }
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "for i in 1..len:",
+            "  lo, hi = 0, i",
+            "  while lo < hi:",
+            "    mid = (lo + hi) / 2",
+            "    if a[mid] <= a[i]:",
+            "      lo = mid + 1",
+            "    else:",
+            "      hi = mid",
+            "  key = a[i]",
+            "  for j in (lo..i).rev():",
+            "    a[j + 1] = a[j]",
+            "  a[lo] = key",
+        ]
+    }
+    fn run(&self, tracer: &mut Tracer) {
+        binary_insertionsort(tracer);
+    }
+}
+
+pub struct SelectionSort;
+
+impl SortAlgorithm for SelectionSort {
+    fn name(&self) -> &'static str {
+        "Selection Sort"
+    }
+    fn description(&self) -> &'static str {
+        "Scans the unsorted part for its minimum and swaps it to the front, \
+         so it needs only one swap per position."
+    }
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "for i in 0..len - 1:",
+            "  min = i",
+            "  for j in i + 1..len:",
+            "    if a[j] < a[min]:",
+            "      min = j",
+            "  swap a[i], a[min]",
+        ]
+    }
+    fn run(&self, tracer: &mut Tracer) {
+        selectionsort(tracer);
+    }
+}
+
+pub struct DoubleSelectionSort;
+
+impl SortAlgorithm for DoubleSelectionSort {
+    fn name(&self) -> &'static str {
+        "Double Selection Sort"
+    }
+    fn description(&self) -> &'static str {
+        "Selection sort that looks for the minimum and the maximum in the same \
+         scan and moves them to both ends of the unsorted part."
+    }
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "lo, hi = 0, len - 1",
+            "while lo < hi:",
+            "  min, max = lo, lo",
+            "  for j in lo + 1..=hi:",
+            "    if a[j] < a[min]: min = j",
+            "    if a[j] > a[max]: max = j",
+            "  swap a[lo], a[min]",
+            "  if max == lo: max = min",
+            "  swap a[hi], a[max]",
+            "  lo, hi = lo + 1, hi - 1",
+        ]
+    }
+    fn run(&self, tracer: &mut Tracer) {
+        double_selectionsort(tracer);
+    }
+}
+
+pub struct MergeSort;
+
+impl SortAlgorithm for MergeSort {
+    fn name(&self) -> &'static str {
+        "Merge Sort"
+    }
+    fn description(&self) -> &'static str {
+        "Splits the values in halves, sorts both recursively and merges them \
+         through a buffer as large as the input."
+    }
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "mergesort(lo, hi):",
+            "  if lo >= hi: return",
+            "  mid = (lo + hi) / 2",
+            "  mergesort(lo, mid)",
+            "  mergesort(mid + 1, hi)",
+            "  merge(lo, mid, hi)",
+            "",
+            "merge(lo, mid, hi):",
+            "  i, j = lo, mid + 1",
+            "  for k in lo..=hi:",
+            "    if j > hi or i <= mid and a[i] <= a[j]:",
+            "      buf[k] = a[i]; i = i + 1",
+            "    else:",
+            "      buf[k] = a[j]; j = j + 1",
+            "  for k in lo..=hi:",
+            "    a[k] = buf[k]",
+        ]
+    }
+    fn run(&self, tracer: &mut Tracer) {
+        let length = tracer.len();
+        tracer.allocate_buffer(length);
+        if length > 1 {
+            mergesort(tracer, 0, length - 1);
+        }
+        settle_all(tracer);
+    }
+}
+
+pub struct BottomUpMergeSort;
+
+impl SortAlgorithm for BottomUpMergeSort {
+    fn name(&self) -> &'static str {
+        "Bottom-up Merge Sort"
+    }
+    fn description(&self) -> &'static str {
+        "Merges neighbouring runs of one value, then of two, four and so on, \
+         without any recursion."
+    }
+    fn pseudocode(&self) -> &'static [&'static str] {
+        &[
+            "width = 1",
+            "while width < len:",
+            "  for lo in (0..len).step_by(2 * width):",
+            "    mid = min(lo + width, len) - 1",
+            "    hi = min(lo + 2 * width, len) - 1",
+            "    merge(lo, mid, hi)",
+            "  width = width * 2",
+            "",
+            "merge(lo, mid, hi):",
+            "  i, j = lo, mid + 1",
+            "  for k in lo..=hi:",
+            "    if j > hi or i <= mid and a[i] <= a[j]:",
+            "      buf[k] = a[i]; i = i + 1",
+            "    else:",
+            "      buf[k] = a[j]; j = j + 1",
+            "  for k in lo..=hi:",
+            "    a[k] = buf[k]",
+        ]
+    }
+    fn run(&self, tracer: &mut Tracer) {
+        bottom_up_mergesort(tracer);
+    }
+}
+
+/// Sort the values of `tracer` using bubble sort.
+pub fn bubblesort(tracer: &mut Tracer) {
+    let length = tracer.len();
     for n in 0..length {
+        // the pass ends before the values that are in place already
+        tracer.range(0, length - n - 1);
         for i in 0..length - n - 1 {
-            if stop_flag.load(Ordering::Relaxed) {
-                return;
-            }
             let j = i + 1;
 
             // clear old highlights, then highlight i and j
-            let mut nums = numbers.lock().unwrap();
-            nums.remove_all_highlights();
-            nums.add_highlight(i, datatypes::Highlight::Primary)
-                .unwrap();
-            nums.add_highlight(j, datatypes::Highlight::Secondary)
-                .unwrap();
-            if nums.values[i].value > nums.values[j].value {
-                nums.values.swap(i, j);
+            tracer.clear_highlights();
+            tracer.highlight(i, datatypes::Highlight::Primary);
+            tracer.highlight(j, datatypes::Highlight::Secondary);
+            tracer.line(2);
+            if tracer.compare(i, j) == Ordering::Greater {
+                tracer.line(3);
+                tracer.swap(i, j);
             }
+        }
+        // every pass moves the largest remaining value to the end
+        tracer.settle(length - n - 1);
+    }
+    tracer.clear_highlights();
+}
 
-            drop(nums);
-            ctx.request_repaint();
-            thread::sleep(time::Duration::from_millis(
-                animation_delay.load(Ordering::Relaxed).into(),
-            ));
+/// Sort the values of `tracer` using insertion sort. The key is held in the
+/// buffer slot below its original position while larger values are shifted.
+pub fn insertionsort(tracer: &mut Tracer) {
+    let length = tracer.len();
+    tracer.allocate_buffer(length);
+    for i in 1..length {
+        tracer.range(0, i);
+        tracer.clear_highlights();
+        tracer.line(1);
+        tracer.store(i, i);
+        let mut j = i;
+        while j > 0 {
+            // clear old highlights, then highlight where the key would go
+            // and the value left of it
+            tracer.clear_highlights();
+            tracer.highlight(j, datatypes::Highlight::Key);
+            tracer.highlight(j - 1, datatypes::Highlight::Primary);
+            tracer.line(3);
+            if tracer.compare_buffer(j - 1, i) != Ordering::Greater {
+                break;
+            }
+            tracer.line(4);
+            tracer.copy(j - 1, j);
+            j -= 1;
         }
+        tracer.clear_highlights();
+        tracer.line(6);
+        tracer.load(i, j);
     }
-    let mut nums = numbers.lock().unwrap();
-    nums.remove_all_highlights();
-    ctx.request_repaint();
+    settle_all(tracer);
 }
 
-pub fn quicksort(
-    numbers: Arc<Mutex<datatypes::NumberVec>>,
-    low_idx: usize,
-    high_idx: usize,
-    animation_delay: &Arc<AtomicU8>,
-    ctx: &egui::Context,
-    stop_flag: &Arc<AtomicBool>,
-) {
-    if low_idx >= high_idx || stop_flag.load(Ordering::Relaxed) {
-        return;
+/// Sort the values of `tracer` using binary insertion sort
+pub fn binary_insertionsort(tracer: &mut Tracer) {
+    let length = tracer.len();
+    tracer.allocate_buffer(length);
+    for i in 1..length {
+        tracer.range(0, i);
+        // the key goes right of all values that are smaller or equal, which
+        // keeps the sort stable
+        let (mut lo, mut hi) = (0, i);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            // clear old highlights, then highlight the key, the search range
+            // and its middle. Nothing moves during the search, so the bounds
+            // can be highlights and the key stays in the active range.
+            tracer.clear_highlights();
+            tracer.highlight(i, datatypes::Highlight::Key);
+            tracer.highlight(lo, datatypes::Highlight::Bound);
+            tracer.highlight(hi - 1, datatypes::Highlight::Bound);
+            tracer.highlight(mid, datatypes::Highlight::Primary);
+            tracer.line(4);
+            if tracer.compare(mid, i) != Ordering::Greater {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        // hold the key in the buffer while making room for it
+        tracer.clear_highlights();
+        tracer.line(8);
+        tracer.store(i, i);
+        for j in (lo..i).rev() {
+            tracer.line(10);
+            tracer.copy(j, j + 1);
+        }
+        tracer.line(11);
+        tracer.load(i, lo);
     }
-    // let mut nums = numbers.lock().unwrap();
-    // nums.highlight_at = Some(high_idx);
-    // drop(nums);
-
-    let pivot_idx = qs_partition(
-        Arc::clone(&numbers),
-        low_idx,
-        high_idx,
-        animation_delay,
-        ctx,
-        stop_flag,
-    );
-    // let mut nums = numbers.lock().unwrap();
-    // nums.highlight_at = None;
-    // drop(nums);
+    settle_all(tracer);
+}
 
-    if pivot_idx == 0 {
-        quicksort(
-            Arc::clone(&numbers),
-            low_idx,
-            0,
-            animation_delay,
-            ctx,
-            stop_flag,
-        );
-        quicksort(
-            Arc::clone(&numbers),
-            pivot_idx + 1,
-            high_idx,
-            animation_delay,
-            ctx,
-            stop_flag,
-        );
-    } else {
-        quicksort(
-            Arc::clone(&numbers),
-            low_idx,
-            pivot_idx - 1,
-            animation_delay,
-            ctx,
-            stop_flag,
-        );
-        quicksort(
-            Arc::clone(&numbers),
-            pivot_idx + 1,
-            high_idx,
-            animation_delay,
-            ctx,
-            stop_flag,
-        );
+/// Sort the values of `tracer` using selection sort
+pub fn selectionsort(tracer: &mut Tracer) {
+    let length = tracer.len();
+    for i in 0..length.saturating_sub(1) {
+        tracer.range(i, length - 1);
+        let mut min = i;
+        for j in i + 1..length {
+            highlight_scan(tracer, &[(min, datatypes::Highlight::Primary)], j);
+            tracer.line(3);
+            if tracer.compare(j, min) == Ordering::Less {
+                min = j;
+            }
+        }
+        tracer.clear_highlights();
+        tracer.highlight(min, datatypes::Highlight::Primary);
+        tracer.line(5);
+        tracer.swap(i, min);
+        tracer.settle(i);
+    }
+    // the largest value is left over at the end
+    if length > 0 {
+        tracer.settle(length - 1);
     }
 }
 
-fn qs_partition(
-    numbers: Arc<Mutex<datatypes::NumberVec>>,
-    low_idx: usize,
-    high_idx: usize,
-    animation_delay: &Arc<AtomicU8>,
-    ctx: &egui::Context,
-    stop_flag: &Arc<AtomicBool>,
-) -> usize {
-    let nums = numbers.lock().unwrap();
-    let pivot_value = nums.values[high_idx].value;
-    drop(nums);
+/// Sort the values of `tracer` using selection sort, moving both the minimum
+/// and the maximum of each scan
+pub fn double_selectionsort(tracer: &mut Tracer) {
+    let Some(mut hi) = tracer.len().checked_sub(1) else {
+        return;
+    };
+    let mut lo = 0;
+    while lo < hi {
+        tracer.range(lo, hi);
+        let (mut min, mut max) = (lo, lo);
+        for j in lo + 1..=hi {
+            highlight_scan(tracer, &extremes(min, max), j);
+            tracer.line(4);
+            if tracer.compare(j, min) == Ordering::Less {
+                min = j;
+            }
+            highlight_scan(tracer, &extremes(min, max), j);
+            tracer.line(5);
+            if tracer.compare(j, max) == Ordering::Greater {
+                max = j;
+            }
+        }
+        tracer.clear_highlights();
+        tracer.highlight(min, datatypes::Highlight::Primary);
+        tracer.highlight(max, datatypes::Highlight::Secondary);
+        tracer.line(6);
+        tracer.swap(lo, min);
+        // the maximum was just swapped away from lo
+        if max == lo {
+            max = min;
+        }
+        tracer.line(8);
+        tracer.swap(hi, max);
+        tracer.settle(lo);
+        tracer.settle(hi);
+        lo += 1;
+        hi -= 1;
+    }
+    // the middle value of an odd length is left over
+    if lo == hi {
+        tracer.settle(lo);
+    }
+}
 
-    // when low_idx == 0, i becomes -1 temporarily
-    let mut i = low_idx as i64 - 1;
+/// Sort the values of `tracer` between `low_idx` and `high_idx` (inclusive)
+/// using merge sort. The buffer has to be allocated already.
+pub fn mergesort(tracer: &mut Tracer, low_idx: usize, high_idx: usize) {
+    if low_idx >= high_idx {
+        return;
+    }
+    let mid_idx = (low_idx + high_idx) / 2;
+    mergesort(tracer, low_idx, mid_idx);
+    mergesort(tracer, mid_idx + 1, high_idx);
+    merge(tracer, low_idx, mid_idx, high_idx, 7);
+}
 
-    for j in low_idx..high_idx {
-        if stop_flag.load(Ordering::Relaxed) {
-            return high_idx;
+/// Sort the values of `tracer` using bottom-up merge sort
+pub fn bottom_up_mergesort(tracer: &mut Tracer) {
+    let length = tracer.len();
+    tracer.allocate_buffer(length);
+    let mut width = 1;
+    while width < length {
+        for low_idx in (0..length).step_by(2 * width) {
+            let mid_idx = (low_idx + width).min(length) - 1;
+            let high_idx = (low_idx + 2 * width).min(length) - 1;
+            // the last run has no neighbour to be merged with
+            if mid_idx < high_idx {
+                merge(tracer, low_idx, mid_idx, high_idx, 8);
+            }
         }
+        width *= 2;
+    }
+    settle_all(tracer);
+}
 
-        // clear old highlights, then highlight i and j
-        let mut nums = numbers.lock().unwrap();
-        nums.remove_all_highlights();
-        if i >= 0 {
-            nums.add_highlight(i as usize, datatypes::Highlight::Primary)
-                .unwrap();
+/// Merge the sorted runs `low_idx..=mid_idx` and `mid_idx + 1..=high_idx`
+/// into the buffer, then copy them back. `first_line` is the index of the
+/// pseudocode line where `merge` starts.
+fn merge(tracer: &mut Tracer, low_idx: usize, mid_idx: usize, high_idx: usize, first_line: usize) {
+    tracer.range(low_idx, high_idx);
+    let (mut i, mut j) = (low_idx, mid_idx + 1);
+    for k in low_idx..=high_idx {
+        // clear old highlights, then highlight the heads of both runs
+        tracer.clear_highlights();
+        if i <= mid_idx {
+            tracer.highlight(i, datatypes::Highlight::Primary);
+        }
+        if j <= high_idx {
+            tracer.highlight(j, datatypes::Highlight::Secondary);
         }
-        nums.add_highlight(j, datatypes::Highlight::Secondary)
-            .unwrap();
-        if nums.values[j].value <= pivot_value {
+        let take_left = if j > high_idx {
+            true
+        } else if i > mid_idx {
+            false
+        } else {
+            tracer.line(first_line + 3);
+            // taking the left value on ties keeps the sort stable
+            tracer.compare(i, j) != Ordering::Greater
+        };
+        if take_left {
+            tracer.line(first_line + 4);
+            tracer.store(i, k);
             i += 1;
-            // i should never be negative at this point
-            nums.values.swap(i as usize, j)
+        } else {
+            tracer.line(first_line + 6);
+            tracer.store(j, k);
+            j += 1;
         }
-        drop(nums);
-        ctx.request_repaint();
-        thread::sleep(time::Duration::from_millis(
-            animation_delay.load(Ordering::Relaxed).into(),
-        ));
     }
+    tracer.clear_highlights();
+    for k in low_idx..=high_idx {
+        tracer.line(first_line + 8);
+        tracer.load(k, k);
+    }
+}
 
-    let mut nums = numbers.lock().unwrap();
-    i += 1;
-    // i should never be negative at this point
-    nums.values.swap(i as usize, high_idx);
-    ctx.request_repaint();
-    i as usize
+/// The running minimum as primary and the running maximum as secondary
+/// highlight
+fn extremes(min: usize, max: usize) -> [(usize, datatypes::Highlight); 2] {
+    [
+        (min, datatypes::Highlight::Primary),
+        (max, datatypes::Highlight::Secondary),
+    ]
 }
 
-pub fn shuffle(numbers: Arc<Mutex<datatypes::NumberVec>>) {
-    let mut rng = thread_rng();
-    let mut nums = numbers.lock().unwrap();
-    nums.remove_all_highlights();
-    nums.values.shuffle(&mut rng);
-    for (i, n) in nums.values.iter_mut().enumerate() {
-        n.color(i as u8);
+/// Replace all highlights with the values selected so far and the one
+/// scanned next, which is the tertiary highlight
+fn highlight_scan(tracer: &mut Tracer, selected: &[(usize, datatypes::Highlight)], scanned: usize) {
+    tracer.clear_highlights();
+    for &(idx, highlight) in selected {
+        tracer.highlight(idx, highlight);
     }
+    tracer.highlight(scanned, datatypes::Highlight::Tertiary);
 }
 
-/// Only exists to test the implementation easily
-fn _basic_quicksort(numbers: &mut datatypes::NumberVec, lo: usize, hi: usize) {
-    if lo >= hi {
+/// Mark all values as final, for algorithms that only know once they are
+/// done
+fn settle_all(tracer: &mut Tracer) {
+    tracer.clear_highlights();
+    for idx in 0..tracer.len() {
+        tracer.settle(idx);
+    }
+}
+
+/// Sort the values of `tracer` between `low_idx` and `high_idx` (inclusive)
+/// using quick sort.
+pub fn quicksort(tracer: &mut Tracer, low_idx: usize, high_idx: usize) {
+    if low_idx >= high_idx {
+        // a single value is sorted already
+        if low_idx == high_idx {
+            tracer.settle(low_idx);
+        }
         return;
     }
+    tracer.range(low_idx, high_idx);
+
+    let pivot_idx = qs_partition(tracer, low_idx, high_idx);
 
-    let p = _basic_qs_partition(numbers, lo, hi);
-    if p == 0 {
-        _basic_quicksort(numbers, lo, p);
-        _basic_quicksort(numbers, p + 1, hi);
+    if pivot_idx == 0 {
+        quicksort(tracer, low_idx, 0);
+        quicksort(tracer, pivot_idx + 1, high_idx);
     } else {
-        _basic_quicksort(numbers, lo, p - 1);
-        _basic_quicksort(numbers, p + 1, hi);
+        quicksort(tracer, low_idx, pivot_idx - 1);
+        quicksort(tracer, pivot_idx + 1, high_idx);
     }
 }
 
-/// Only exists to test the implementation easily
-fn _basic_qs_partition(numbers: &mut datatypes::NumberVec, lo: usize, hi: usize) -> usize {
-    let pivot = numbers.values[hi].value;
-    println!("pivot value {pivot:?} (index {hi:?})");
-    // when lo == 0, i becomes -1 temporarily
-    let mut i = lo as i64 - 1;
-    for j in lo..hi {
-        let looking_at = numbers.values[j].value;
-        if numbers.values[j].value <= pivot {
-            println!("Incrementing i from {i:?}, {looking_at:?} <= {pivot:?}");
+fn qs_partition(tracer: &mut Tracer, low_idx: usize, high_idx: usize) -> usize {
+    // the pivot stays at high_idx until the loop is done, since only indices
+    // below high_idx are swapped
+    let pivot_idx = high_idx;
+
+    // when low_idx == 0, i becomes -1 temporarily
+    let mut i = low_idx as i64 - 1;
+
+    for j in low_idx..high_idx {
+        // clear old highlights, then highlight the pivot, i and j
+        tracer.clear_highlights();
+        tracer.highlight(pivot_idx, datatypes::Highlight::Pivot);
+        if i >= 0 {
+            tracer.highlight(i as usize, datatypes::Highlight::Primary);
+        }
+        tracer.highlight(j, datatypes::Highlight::Secondary);
+        tracer.line(10);
+        if tracer.compare(j, pivot_idx) != Ordering::Greater {
             i += 1;
             // i should never be negative at this point
-            numbers.values.swap(i as usize, j)
+            tracer.line(12);
+            tracer.swap(i as usize, j)
         }
     }
+
     i += 1;
     // i should never be negative at this point
-    numbers.values.swap(i as usize, hi);
+    tracer.line(13);
+    tracer.swap(i as usize, high_idx);
+    // the pivot is in its final position now
+    tracer.clear_highlights();
+    tracer.highlight(i as usize, datatypes::Highlight::Pivot);
+    tracer.settle(i as usize);
     i as usize
 }
 
@@ -232,6 +619,19 @@ fn _basic_qs_partition(numbers: &mut datatypes::NumberVec, lo: usize, hi: usize)
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::player::Timeline;
+    use crate::trace::Operation;
+    use rand::prelude::*;
+
+    /// Record `algorithm` on `numbers`, then play the whole run on them
+    fn sort_traced(
+        algorithm: &'static dyn SortAlgorithm,
+        numbers: &mut datatypes::NumberVec,
+    ) -> Timeline {
+        let mut timeline = Timeline::record(algorithm, numbers.clone());
+        timeline.seek(timeline.len(), numbers);
+        timeline
+    }
 
     #[test]
     fn quicksort_shuffled_input() {
@@ -239,28 +639,112 @@ mod tests {
         let mut rng = thread_rng();
         numbers.values.shuffle(&mut rng);
 
-        let length = numbers.values.len();
-        _basic_quicksort(&mut numbers, 0, length - 1);
+        sort_traced(&QuickSort, &mut numbers);
         assert!(numbers.is_sorted());
     }
     #[test]
     fn quicksort_sorted_input() {
         let mut numbers = datatypes::NumberVec::new((1..=100).collect());
 
-        let length = numbers.values.len();
-        _basic_quicksort(&mut numbers, 0, length - 1);
+        sort_traced(&QuickSort, &mut numbers);
         assert!(numbers.is_sorted());
     }
     #[test]
     fn quicksort_sorted_reversed_input() {
         let mut numbers = datatypes::NumberVec::new((1..=100).rev().collect());
 
-        let length = numbers.values.len();
-        _basic_quicksort(&mut numbers, 0, length - 1);
+        sort_traced(&QuickSort, &mut numbers);
         println!(
             "{:?}",
-            numbers.values.iter().map(|n| n.value).collect::<Vec<u8>>()
+            numbers.values.iter().map(|n| n.value).collect::<Vec<u32>>()
         );
         assert!(numbers.is_sorted());
     }
+    #[test]
+    fn bubblesort_shuffled_input() {
+        let mut numbers = datatypes::NumberVec::new((1..=100).collect());
+        let mut rng = thread_rng();
+        numbers.values.shuffle(&mut rng);
+
+        sort_traced(&BubbleSort, &mut numbers);
+        assert!(numbers.is_sorted());
+    }
+
+    #[test]
+    fn insertion_sort_does_not_shift_sorted_input() {
+        let mut tracer = Tracer::new((1..=100).collect());
+        insertionsort(&mut tracer);
+        let counters = tracer.finish().counters();
+        assert_eq!(counters.comparisons, 99);
+        assert_eq!(counters.swaps, 0);
+        // only holding each key and putting it back
+        assert_eq!(counters.writes, 2 * 99);
+    }
+
+    #[test]
+    fn selection_sort_swaps_once_per_position() {
+        let mut tracer = Tracer::new((1..=100).rev().collect());
+        selectionsort(&mut tracer);
+        assert_eq!(tracer.finish().counters().swaps, 99);
+    }
+
+    #[test]
+    fn merge_sort_empties_its_buffer() {
+        for algorithm in [&MergeSort as &dyn SortAlgorithm, &BottomUpMergeSort] {
+            let mut numbers = datatypes::NumberVec::new((1..=37).rev().collect());
+            sort_traced(algorithm, &mut numbers);
+            assert!(numbers.is_sorted(), "{} failed", algorithm.name());
+            assert_eq!(numbers.buffer.len(), 37);
+            assert!(numbers.buffer.iter().all(Option::is_none));
+        }
+    }
+
+    #[test]
+    fn algorithms_can_be_found_by_name() {
+        for algorithm in ALGORITHMS {
+            assert_eq!(find(algorithm.name()).unwrap().name(), algorithm.name());
+        }
+        assert_eq!(find("quicksort").unwrap().name(), "Quick Sort");
+        assert_eq!(find("bubble-sort").unwrap().name(), "Bubble Sort");
+        assert!(find("bogo sort").is_none());
+    }
+
+    #[test]
+    fn all_algorithms_sort_shuffled_input() {
+        let distinct: Vec<u32> = (1..=100).collect();
+        let duplicates: Vec<u32> = (1..=100).map(|v| v % 7).collect();
+        for (algorithm, values) in ALGORITHMS
+            .iter()
+            .flat_map(|a| [(a, distinct.clone()), (a, duplicates.clone())])
+        {
+            let mut numbers = datatypes::NumberVec::new(values);
+            let mut rng = thread_rng();
+            numbers.values.shuffle(&mut rng);
+            let colors = |numbers: &datatypes::NumberVec| {
+                let mut colors: Vec<(u32, u32)> = numbers
+                    .values
+                    .iter()
+                    .map(|n| (n.value, n.shade.to_bits()))
+                    .collect();
+                colors.sort_unstable();
+                colors
+            };
+            let before = colors(&numbers);
+
+            let timeline = sort_traced(*algorithm, &mut numbers);
+            for op in timeline.operations() {
+                if let Operation::Line(line) = op {
+                    assert!(*line < algorithm.pseudocode().len(), "{}", algorithm.name());
+                }
+            }
+            assert!(numbers.is_sorted(), "{} failed", algorithm.name());
+            assert!(
+                numbers.values.iter().all(|n| n.settled),
+                "{} left values unsettled",
+                algorithm.name()
+            );
+            // values keep their colors wherever they are moved
+            assert_eq!(colors(&numbers), before, "{}", algorithm.name());
+        }
+    }
 }
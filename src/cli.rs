@@ -0,0 +1,345 @@
+//! Command line interface to run algorithms without opening a window
+//!
+//! Without a subcommand, `visu` starts the GUI. Everything else runs without
+//! an egui context and without any animation delay, so it works on machines
+//! without a display, e.g. in CI.
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use crate::{algos, datatypes, export, inputs, names, player, render, sound};
+
+#[derive(Parser)]
+#[command(version, about = "Visualize algorithms")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run an algorithm, print its operation counts and verify the result
+    Run(RunArgs),
+    /// Render a run as an animated GIF
+    Gif(GifArgs),
+    /// Render a run as numbered PNG frames, e.g. to encode a video
+    Frames(FramesArgs),
+    /// Render the sound of a run as a WAV file
+    Wav(WavArgs),
+}
+
+/// Which algorithm to run on which input
+#[derive(Args)]
+pub struct RunArgs {
+    /// Algorithm to run, e.g. "quicksort"
+    #[arg(long, value_parser = parse_name("algorithm", algos::ALGORITHMS, |a| a.name()))]
+    algo: &'static dyn algos::SortAlgorithm,
+    /// Number of values to sort
+    #[arg(long, default_value_t = 25)]
+    size: usize,
+    /// Seed for the input, the same seed always yields the same input
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Shape of the input, e.g. "random" or "nearly-sorted"
+    #[arg(
+        long,
+        value_parser = parse_name("input", &inputs::InputPreset::ALL, inputs::InputPreset::name),
+        default_value = "random"
+    )]
+    input: inputs::InputPreset,
+}
+
+/// Colors of rendered images
+#[derive(Args)]
+pub struct StyleArgs {
+    /// Colors of the bars, e.g. "classic" or the colorblind safe "viridis"
+    #[arg(
+        long,
+        value_parser = parse_name("palette", &render::Palette::ALL, render::Palette::name),
+        default_value = "classic"
+    )]
+    palette: render::Palette,
+    /// Dark background
+    #[arg(long)]
+    dark: bool,
+}
+
+impl StyleArgs {
+    fn style(&self) -> render::Style {
+        render::Style::new(self.palette, self.dark)
+    }
+}
+
+#[derive(Args)]
+pub struct GifArgs {
+    #[command(flatten)]
+    run: RunArgs,
+    #[command(flatten)]
+    style: StyleArgs,
+    /// File to write the GIF to
+    #[arg(long, short)]
+    output: PathBuf,
+    /// Only render every Nth step
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    every: usize,
+    #[arg(long, default_value_t = 640, value_parser = clap::value_parser!(u16).range(1..))]
+    width: u16,
+    #[arg(long, default_value_t = 360, value_parser = clap::value_parser!(u16).range(1..))]
+    height: u16,
+    /// Time in ms each frame is shown
+    #[arg(long, default_value_t = 50)]
+    frame_delay: u16,
+}
+
+#[derive(Args)]
+pub struct FramesArgs {
+    #[command(flatten)]
+    run: RunArgs,
+    #[command(flatten)]
+    style: StyleArgs,
+    /// Directory to write the frames to, created if it doesn't exist
+    #[arg(long, short)]
+    output: PathBuf,
+    #[arg(long, default_value_t = 1280, value_parser = clap::value_parser!(u32).range(1..))]
+    width: u32,
+    #[arg(long, default_value_t = 720, value_parser = clap::value_parser!(u32).range(1..))]
+    height: u32,
+    /// Frame rate of the video
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..))]
+    fps: u32,
+    /// Steps of the run played per second of video
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..))]
+    steps_per_second: u32,
+    /// Draw the algorithm name and the counters above the bars
+    #[arg(long)]
+    overlay: bool,
+}
+
+#[derive(Args)]
+pub struct WavArgs {
+    #[command(flatten)]
+    run: RunArgs,
+    /// File to write the WAV to
+    #[arg(long, short)]
+    output: PathBuf,
+    /// Time in ms each step takes
+    #[arg(long, default_value_t = 20)]
+    step_duration: u64,
+    #[arg(long, default_value_t = 44100, value_parser = clap::value_parser!(u32).range(1..))]
+    sample_rate: u32,
+    /// Between 0 and 1
+    #[arg(long, default_value_t = 0.3)]
+    volume: f32,
+    /// Shape of the tones, e.g. "sine" or "square"
+    #[arg(
+        long,
+        value_parser = parse_name("waveform", &sound::Waveform::ALL, sound::Waveform::name),
+        default_value = "sine"
+    )]
+    waveform: sound::Waveform,
+}
+
+impl RunArgs {
+    /// Record the algorithm on the generated input.
+    ///
+    /// Returns the timeline of the run at position 0 and the input numbers.
+    fn record(&self) -> (player::Timeline, datatypes::NumberVec) {
+        let numbers = datatypes::NumberVec::new(self.input.generate(self.size, self.seed));
+        (
+            player::Timeline::record(self.algo, numbers.clone()),
+            numbers,
+        )
+    }
+}
+
+/// Value parser for options that select one of `items` by name, see
+/// `names::find`. Unknown names fail with a list of all valid ones.
+fn parse_name<T: Copy + Send + Sync + 'static>(
+    kind: &'static str,
+    items: &'static [T],
+    name_of: fn(&T) -> &'static str,
+) -> impl Fn(&str) -> Result<T, String> + Clone {
+    move |name| {
+        names::find(items, name, name_of).ok_or_else(|| {
+            let available: Vec<String> =
+                items.iter().map(|i| names::normalize(name_of(i))).collect();
+            format!("unknown {kind}, available: {}", available.join(", "))
+        })
+    }
+}
+
+/// Execute `command` and return the exit code of the process
+pub fn run(command: Command) -> i32 {
+    match command {
+        Command::Run(args) => run_algorithm(&args),
+        Command::Gif(args) => export_gif(&args),
+        Command::Frames(args) => export_frames(&args),
+        Command::Wav(args) => export_wav(&args),
+    }
+}
+
+/// Apply the whole run to the input, then print counters and whether the
+/// result is sorted. Fails if it isn't.
+fn run_algorithm(args: &RunArgs) -> i32 {
+    let (mut timeline, mut numbers) = args.record();
+    timeline.seek(timeline.len(), &mut numbers);
+    let counters = timeline.counters();
+    let sorted = numbers.is_sorted();
+
+    println!("algorithm: {}", args.algo.name());
+    println!("input: {}", args.input.name());
+    println!("size: {}", args.size);
+    println!("seed: {}", args.seed);
+    println!("comparisons: {}", counters.comparisons);
+    println!("swaps: {}", counters.swaps);
+    println!("reads: {}", counters.reads);
+    println!("writes: {}", counters.writes);
+    println!("steps: {}", counters.steps);
+    println!("sorted: {sorted}");
+    if sorted {
+        0
+    } else {
+        1
+    }
+}
+
+fn export_gif(args: &GifArgs) -> i32 {
+    let (timeline, _) = args.run.record();
+    let options = export::GifOptions {
+        width: args.width,
+        height: args.height,
+        every: args.every,
+        frame_delay_ms: args.frame_delay,
+        style: args.style.style(),
+    };
+    match export::save_gif(&args.output, &timeline, &options, &AtomicBool::new(false)) {
+        Ok(()) => {
+            let frames = export::sampled_steps(timeline.len(), args.every).len();
+            println!("wrote {frames} frames to {}", args.output.display());
+            0
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            1
+        }
+    }
+}
+
+fn export_frames(args: &FramesArgs) -> i32 {
+    let (timeline, _) = args.run.record();
+    let options = export::FrameOptions {
+        width: args.width,
+        height: args.height,
+        fps: args.fps,
+        steps_per_second: args.steps_per_second,
+        overlay: args.overlay,
+        style: args.style.style(),
+    };
+    let title = format!(
+        "{} - {}, seed {}",
+        args.run.algo.name(),
+        args.run.input.name(),
+        args.run.seed
+    );
+    match export::save_frames(
+        &args.output,
+        &timeline,
+        &options,
+        &title,
+        &AtomicBool::new(false),
+    ) {
+        Ok(frames) => {
+            println!("wrote {frames} frames to {}", args.output.display());
+            println!(
+                "encode with: ffmpeg -framerate {} -i {}/frame-%05d.png video.mp4",
+                args.fps,
+                args.output.display()
+            );
+            0
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            1
+        }
+    }
+}
+
+fn export_wav(args: &WavArgs) -> i32 {
+    let (timeline, _) = args.run.record();
+    let options = export::WavOptions {
+        sample_rate: args.sample_rate,
+        step_duration: Duration::from_millis(args.step_duration),
+        settings: sound::SoundSettings {
+            volume: args.volume.clamp(0., 1.),
+            waveform: args.waveform,
+        },
+    };
+    match export::save_wav(&args.output, &timeline, &options) {
+        Ok(samples) => {
+            let seconds = samples as f64 / args.sample_rate as f64;
+            println!("wrote {seconds:.2}s of audio to {}", args.output.display());
+            0
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_accepts_algorithm_and_input_names() {
+        let cli = Cli::try_parse_from([
+            "visu",
+            "run",
+            "--algo",
+            "quicksort",
+            "--size",
+            "100",
+            "--seed",
+            "7",
+            "--input",
+            "reversed",
+        ])
+        .unwrap();
+        let Some(Command::Run(args)) = cli.command else {
+            panic!("expected run subcommand");
+        };
+        assert_eq!(args.algo.name(), "Quick Sort");
+        assert_eq!(args.input, inputs::InputPreset::Reversed);
+        assert_eq!(run_algorithm(&args), 0);
+    }
+
+    #[test]
+    fn run_rejects_unknown_algorithm() {
+        assert!(Cli::try_parse_from(["visu", "run", "--algo", "bogosort"]).is_err());
+    }
+
+    #[test]
+    fn exports_reject_zero_rates_and_sizes() {
+        let wav = ["visu", "wav", "--algo", "quicksort", "-o", "a.wav"];
+        assert!(Cli::try_parse_from(wav).is_ok());
+        assert!(Cli::try_parse_from(wav.into_iter().chain(["--sample-rate", "0"])).is_err());
+        let frames = ["visu", "frames", "--algo", "quicksort", "-o", "frames"];
+        assert!(Cli::try_parse_from(frames.into_iter().chain(["--fps", "0"])).is_err());
+        assert!(Cli::try_parse_from(frames.into_iter().chain(["--width", "0"])).is_err());
+        assert!(Cli::try_parse_from(frames.into_iter().chain(["--height", "0"])).is_err());
+    }
+
+    #[test]
+    fn gifs_reject_zero_sizes() {
+        let gif = ["visu", "gif", "--algo", "quicksort", "-o", "a.gif"];
+        assert!(Cli::try_parse_from(gif).is_ok());
+        assert!(Cli::try_parse_from(gif.into_iter().chain(["--width", "0"])).is_err());
+        assert!(Cli::try_parse_from(gif.into_iter().chain(["--height", "0"])).is_err());
+    }
+}
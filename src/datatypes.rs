@@ -1,37 +1,92 @@
-use eframe::egui;
-
-#[derive(Debug)]
+/// Kinds of highlights, in the order of their importance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Highlight {
-    None,
     Primary,
     Secondary,
+    Tertiary,
+    /// The value a range is partitioned around
+    Pivot,
+    /// The value currently being inserted
+    Key,
+    /// First or last index of a range, shown for `NumberVec::range`
+    Bound,
+    /// The value is in its final position, shown for all `Number::settled`
+    Sorted,
 }
 
-#[derive(Debug)]
+impl Highlight {
+    pub const ALL: [Highlight; 7] = [
+        Highlight::Primary,
+        Highlight::Secondary,
+        Highlight::Tertiary,
+        Highlight::Pivot,
+        Highlight::Key,
+        Highlight::Bound,
+        Highlight::Sorted,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Highlight::Primary => "Primary",
+            Highlight::Secondary => "Secondary",
+            Highlight::Tertiary => "Tertiary",
+            Highlight::Pivot => "Pivot",
+            Highlight::Key => "Key",
+            Highlight::Bound => "Range bound",
+            Highlight::Sorted => "Final position",
+        }
+    }
+
+    fn bit(&self) -> u8 {
+        1 << *self as u8
+    }
+}
+
+/// Set of highlights of a single number, which can carry several at once
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Highlights(u8);
+
+impl Highlights {
+    pub fn insert(&mut self, highlight: Highlight) {
+        self.0 |= highlight.bit();
+    }
+    pub fn contains(&self, highlight: Highlight) -> bool {
+        self.0 & highlight.bit() != 0
+    }
+    /// All highlights in the set, the most important one first
+    pub fn iter(&self) -> impl Iterator<Item = Highlight> + '_ {
+        Highlight::ALL.into_iter().filter(|h| self.contains(*h))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Number {
-    pub value: u8,
-    pub color: egui::Color32,
-    pub highlight: Highlight,
+    pub value: u32,
+    /// Position in the input between 0 (first) and 1 (last), which
+    /// `render::Palette` turns into a color. Numbers keep it while they are
+    /// moved around, so they stay recognizable.
+    pub shade: f32,
+    pub highlights: Highlights,
+    /// The algorithm proved that the number is in its final position. Unlike
+    /// highlights, this stays until the next run is recorded.
+    pub settled: bool,
 }
 
 impl Number {
-    pub fn new(value: u8, color_multiplier: u8) -> Self {
+    /// Create a number shaded for `position` out of `length` positions
+    pub fn new(value: u32, position: usize, length: usize) -> Self {
         Self {
             value,
-            color: Number::calculate_color(color_multiplier),
-            highlight: Highlight::None,
+            shade: Number::calculate_shade(position, length),
+            highlights: Highlights::default(),
+            settled: false,
         }
     }
-    pub fn color(&mut self, color_multiplier: u8) {
-        self.color = Number::calculate_color(color_multiplier);
-    }
 
-    fn calculate_color(color_multiplier: u8) -> egui::Color32 {
-        let mut red_value = 255 - 5 * color_multiplier as i16;
-        if red_value < 0 {
-            red_value = 0
-        }
-        egui::Color32::from_rgb(red_value as u8, 100, 100)
+    /// Spread the shades over all positions, so every number has a distinct
+    /// color regardless of the length
+    fn calculate_shade(position: usize, length: usize) -> f32 {
+        (position as f32 / length.saturating_sub(1).max(1) as f32).min(1.)
     }
 }
 
@@ -46,18 +101,31 @@ impl PartialEq for Number {
     }
 }
 
+#[derive(Clone)]
 pub struct NumberVec {
     pub values: Vec<Number>,
+    /// Inclusive range of indices the running algorithm currently works on
+    pub range: Option<(usize, usize)>,
+    /// Index of the pseudocode line the running algorithm is at, see
+    /// `algos::SortAlgorithm::pseudocode`
+    pub line: Option<usize>,
+    /// Auxiliary array of algorithms that don't sort in place, empty for all
+    /// others. Slot `i` is drawn below value `i`, empty slots are `None`.
+    pub buffer: Vec<Option<Number>>,
 }
 
 impl NumberVec {
-    pub fn new(values: Vec<u8>) -> Self {
+    pub fn new(values: Vec<u32>) -> Self {
+        let length = values.len();
         Self {
             values: values
                 .iter()
                 .enumerate()
-                .map(|(i, v)| Number::new(*v, i as u8))
+                .map(|(i, v)| Number::new(*v, i, length))
                 .collect(),
+            range: None,
+            line: None,
+            buffer: vec![],
         }
     }
     pub fn is_sorted(&self) -> bool {
@@ -68,11 +136,46 @@ impl NumberVec {
         }
         true
     }
+    /// Remove everything a previous run left behind besides the order of the
+    /// numbers, so a new run can be recorded from a clean state
+    pub fn clear_run_state(&mut self) {
+        self.remove_all_highlights();
+        for num in self.values.iter_mut() {
+            num.settled = false;
+        }
+        self.range = None;
+        self.line = None;
+        self.buffer.clear();
+    }
     pub fn remove_all_highlights(&mut self) {
-        for mut num in self.values.iter_mut() {
-            num.highlight = Highlight::None;
+        for num in self.values.iter_mut() {
+            num.highlights = Highlights::default();
         }
     }
+    /// Copy the number at `from` over the one at `to`, without its markers
+    pub fn copy(&mut self, from: usize, to: usize) {
+        self.values[to] = Number {
+            highlights: Highlights::default(),
+            settled: false,
+            ..self.values[from].clone()
+        };
+    }
+    /// Copy the number at `idx` into buffer slot `slot`, without its markers
+    pub fn store(&mut self, idx: usize, slot: usize) {
+        self.buffer[slot] = Some(Number {
+            highlights: Highlights::default(),
+            settled: false,
+            ..self.values[idx].clone()
+        });
+    }
+    /// Move the number in buffer slot `slot` back to `idx`, leaving the slot
+    /// empty
+    pub fn load(&mut self, slot: usize, idx: usize) {
+        if let Some(num) = self.buffer[slot].take() {
+            self.values[idx] = num;
+        }
+    }
+    /// Add `highlight` to the number at `idx`, keeping its other highlights
     pub fn add_highlight(
         &mut self,
         idx: usize,
@@ -84,7 +187,7 @@ impl NumberVec {
                 maximum: self.values.len() - 1,
             });
         }
-        self.values[idx].highlight = highlight;
+        self.values[idx].highlights.insert(highlight);
         Ok(())
     }
 }
@@ -113,4 +216,33 @@ mod tests {
         let descending = NumberVec::new((1..=100).rev().collect());
         assert!(!descending.is_sorted());
     }
+
+    #[test]
+    fn clearing_run_state_keeps_the_order() {
+        let mut numbers = NumberVec::new(vec![2, 1]);
+        numbers.buffer = vec![None; 2];
+        numbers.store(0, 1);
+        numbers.range = Some((0, 1));
+        numbers.line = Some(3);
+        numbers.add_highlight(1, Highlight::Key).unwrap();
+        numbers.values[0].settled = true;
+        numbers.clear_run_state();
+        assert!(!numbers.values[0].settled);
+        assert!(numbers.buffer.is_empty());
+        assert_eq!((numbers.range, numbers.line), (None, None));
+        assert_eq!(numbers.values[1].highlights, Highlights::default());
+        assert_eq!(numbers.values[0].value, 2);
+    }
+
+    #[test]
+    fn numbers_carry_several_highlights() {
+        let mut numbers = NumberVec::new(vec![1, 2]);
+        numbers.add_highlight(0, Highlight::Bound).unwrap();
+        numbers.add_highlight(0, Highlight::Pivot).unwrap();
+        let highlights: Vec<Highlight> = numbers.values[0].highlights.iter().collect();
+        assert_eq!(highlights, [Highlight::Pivot, Highlight::Bound]);
+        assert_eq!(numbers.values[1].highlights, Highlights::default());
+        numbers.remove_all_highlights();
+        assert_eq!(numbers.values[0].highlights, Highlights::default());
+    }
 }
@@ -0,0 +1,372 @@
+//! Offline export of recorded runs
+//!
+//! Exports replay a `player::Timeline` from its start and render the steps
+//! with `render::Canvas`, so they don't need a screen, a GPU or a running UI.
+//! Snapshots store a single state of a panel as PNG and SVG, frame sequences
+//! store a whole run as numbered PNGs for external video encoders. The sound
+//! of a run can be rendered as WAV with the same tones as live playback.
+use std::fs::File;
+use std::io::{BufWriter, Seek, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::render::{self, Canvas, Style};
+use crate::{datatypes, player, sound};
+
+#[derive(Debug)]
+pub enum ExportError {
+    Io(std::io::Error),
+    Gif(gif::EncodingError),
+    Png(png::EncodingError),
+    Wav(hound::Error),
+    /// The export was cancelled before all frames were written
+    Cancelled,
+}
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::Io(err) => write!(f, "Could not write file: {err}"),
+            ExportError::Gif(err) => write!(f, "Could not encode GIF: {err}"),
+            ExportError::Png(err) => write!(f, "Could not encode PNG: {err}"),
+            ExportError::Wav(err) => write!(f, "Could not encode WAV: {err}"),
+            ExportError::Cancelled => write!(f, "Export cancelled"),
+        }
+    }
+}
+impl std::error::Error for ExportError {}
+impl From<std::io::Error> for ExportError {
+    fn from(err: std::io::Error) -> Self {
+        ExportError::Io(err)
+    }
+}
+impl From<gif::EncodingError> for ExportError {
+    fn from(err: gif::EncodingError) -> Self {
+        ExportError::Gif(err)
+    }
+}
+impl From<png::EncodingError> for ExportError {
+    fn from(err: png::EncodingError) -> Self {
+        ExportError::Png(err)
+    }
+}
+impl From<hound::Error> for ExportError {
+    fn from(err: hound::Error) -> Self {
+        ExportError::Wav(err)
+    }
+}
+
+pub struct GifOptions {
+    pub width: u16,
+    pub height: u16,
+    /// Only every `every`th step is rendered
+    pub every: usize,
+    /// How long each frame is shown
+    pub frame_delay_ms: u16,
+    pub style: Style,
+}
+
+/// Steps to render when only rendering every `every`th one. The first and
+/// the last step are always included.
+pub fn sampled_steps(len: usize, every: usize) -> Vec<usize> {
+    let mut steps: Vec<usize> = (0..=len).step_by(every.max(1)).collect();
+    if steps.last() != Some(&len) {
+        steps.push(len);
+    }
+    steps
+}
+
+/// Render `timeline` from its first to its last step as an animated GIF.
+///
+/// Setting `cancel` stops the export before the next frame.
+pub fn write_gif<W: Write>(
+    writer: W,
+    timeline: &player::Timeline,
+    options: &GifOptions,
+    cancel: &AtomicBool,
+) -> Result<(), ExportError> {
+    let mut encoder = gif::Encoder::new(writer, options.width, options.height, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    let mut timeline = timeline.clone();
+    let mut numbers = timeline.initial().clone();
+    timeline.seek(0, &mut numbers);
+    for step in sampled_steps(timeline.len(), options.every) {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(ExportError::Cancelled);
+        }
+        timeline.seek(step, &mut numbers);
+        let mut canvas = Canvas::with_bars(
+            &numbers,
+            options.width.into(),
+            options.height.into(),
+            &options.style,
+        );
+        // quantizes to a palette of at most 256 colors per frame
+        let mut frame =
+            gif::Frame::from_rgba_speed(options.width, options.height, &mut canvas.pixels, 10);
+        // GIF delays are in units of 10ms
+        frame.delay = options.frame_delay_ms / 10;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+/// Write `timeline` as an animated GIF to the file at `path`
+pub fn save_gif(
+    path: &Path,
+    timeline: &player::Timeline,
+    options: &GifOptions,
+    cancel: &AtomicBool,
+) -> Result<(), ExportError> {
+    let file = BufWriter::new(File::create(path)?);
+    write_gif(file, timeline, options, cancel)
+}
+
+#[derive(Clone)]
+pub struct FrameOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Frame rate of the video the frames are meant for
+    pub fps: u32,
+    /// How many steps of the run are played per second of video
+    pub steps_per_second: u32,
+    /// Draw the algorithm name and the counters above the bars
+    pub overlay: bool,
+    pub style: Style,
+}
+
+impl Default for FrameOptions {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+            fps: 30,
+            steps_per_second: 30,
+            overlay: true,
+            style: Style::default(),
+        }
+    }
+}
+
+/// Step shown in each frame when playing `len` steps at `steps_per_second`
+/// in a video with `fps` frames per second. Steps repeat if there are more
+/// frames than steps and are skipped if there are fewer. The first and the
+/// last step are always included.
+pub fn frame_steps(len: usize, fps: u32, steps_per_second: u32) -> Vec<usize> {
+    let (len, fps, sps) = (
+        len as u64,
+        fps.max(1) as u64,
+        steps_per_second.max(1) as u64,
+    );
+    let frames = (len * fps).div_ceil(sps);
+    (0..=frames)
+        .map(|frame| (frame * sps / fps).min(len) as usize)
+        .collect()
+}
+
+/// Render `timeline` as numbered PNG frames into `dir`, which is created if
+/// needed. With `options.overlay`, `title` and the counters are drawn above
+/// the bars. Setting `cancel` stops the export before the next frame, frames
+/// written until then are kept.
+///
+/// Returns the number of written frames.
+pub fn save_frames(
+    dir: &Path,
+    timeline: &player::Timeline,
+    options: &FrameOptions,
+    title: &str,
+    cancel: &AtomicBool,
+) -> Result<usize, ExportError> {
+    std::fs::create_dir_all(dir)?;
+    let mut timeline = timeline.clone();
+    let mut numbers = timeline.initial().clone();
+    timeline.seek(0, &mut numbers);
+    let steps = frame_steps(timeline.len(), options.fps, options.steps_per_second);
+    for (frame, &step) in steps.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(ExportError::Cancelled);
+        }
+        timeline.seek(step, &mut numbers);
+        let lines = if options.overlay {
+            let counters = timeline.counters();
+            vec![
+                title.to_string(),
+                format!("Step {}: {counters}", counters.steps),
+            ]
+        } else {
+            vec![]
+        };
+        let canvas = Canvas::with_bars_and_text(
+            &numbers,
+            options.width,
+            options.height,
+            &lines,
+            &options.style,
+        );
+        let path = dir.join(format!("frame-{frame:05}.png"));
+        write_png(BufWriter::new(File::create(path)?), &canvas)?;
+    }
+    Ok(steps.len())
+}
+
+/// Encode `canvas` as a PNG image
+pub fn write_png<W: Write>(writer: W, canvas: &Canvas) -> Result<(), ExportError> {
+    let mut encoder = png::Encoder::new(writer, canvas.width, canvas.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&canvas.pixels)?;
+    Ok(())
+}
+
+/// Write `numbers` below the text `lines` as `<path>.png` and `<path>.svg`
+pub fn save_snapshot(
+    path: &Path,
+    numbers: &datatypes::NumberVec,
+    width: u32,
+    height: u32,
+    lines: &[String],
+    style: &Style,
+) -> Result<(), ExportError> {
+    let canvas = Canvas::with_bars_and_text(numbers, width, height, lines, style);
+    write_png(
+        BufWriter::new(File::create(path.with_extension("png"))?),
+        &canvas,
+    )?;
+    std::fs::write(
+        path.with_extension("svg"),
+        render::svg(numbers, width, height, lines, style),
+    )?;
+    Ok(())
+}
+
+pub struct WavOptions {
+    pub sample_rate: u32,
+    /// Time each step takes, the tones of a step last exactly as long
+    pub step_duration: Duration,
+    pub settings: sound::SoundSettings,
+}
+
+/// Render the tones of all steps of `timeline` as a mono 16 bit WAV.
+///
+/// Every step takes the same time, so the result only depends on the run and
+/// `options`. Returns the number of written samples.
+pub fn write_wav<W: Write + Seek>(
+    writer: W,
+    timeline: &player::Timeline,
+    options: &WavOptions,
+) -> Result<usize, ExportError> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: options.sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut wav = hound::WavWriter::new(writer, spec)?;
+    let mut mixer = sound::Mixer::new(options.sample_rate, options.settings);
+    let step_samples = (options.step_duration.as_secs_f64() * options.sample_rate as f64) as usize;
+
+    let mut timeline = timeline.clone();
+    let mut numbers = timeline.initial().clone();
+    timeline.seek(0, &mut numbers);
+    for step in 1..=timeline.len() {
+        timeline.seek(step, &mut numbers);
+        if let Some(op) = timeline.step_operation() {
+            for frequency in sound::step_frequencies(op, &numbers) {
+                mixer.play(frequency, options.step_duration);
+            }
+        }
+        for _ in 0..step_samples {
+            wav.write_sample((mixer.next_sample() * i16::MAX as f32) as i16)?;
+        }
+    }
+    wav.finalize()?;
+    Ok(timeline.len() * step_samples)
+}
+
+/// Write the sound of `timeline` as WAV to the file at `path`
+pub fn save_wav(
+    path: &Path,
+    timeline: &player::Timeline,
+    options: &WavOptions,
+) -> Result<usize, ExportError> {
+    write_wav(BufWriter::new(File::create(path)?), timeline, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{algos, datatypes};
+
+    #[test]
+    fn sampled_steps_include_first_and_last() {
+        assert_eq!(sampled_steps(10, 4), vec![0, 4, 8, 10]);
+        assert_eq!(sampled_steps(8, 4), vec![0, 4, 8]);
+        assert_eq!(sampled_steps(0, 1), vec![0]);
+    }
+
+    #[test]
+    fn gif_contains_one_frame_per_sampled_step() {
+        let numbers = datatypes::NumberVec::new(vec![3, 1, 2]);
+        let timeline = player::Timeline::record(&algos::BubbleSort, numbers);
+        let options = GifOptions {
+            width: 30,
+            height: 20,
+            every: 2,
+            frame_delay_ms: 50,
+            style: Style::default(),
+        };
+        let mut gif = vec![];
+        write_gif(&mut gif, &timeline, &options, &AtomicBool::new(false)).unwrap();
+
+        let mut decoder = gif::DecodeOptions::new().read_info(&gif[..]).unwrap();
+        let mut frames = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            frames += 1;
+        }
+        assert_eq!(frames, sampled_steps(timeline.len(), 2).len());
+
+        let cancelled = write_gif(vec![], &timeline, &options, &AtomicBool::new(true));
+        assert!(matches!(cancelled, Err(ExportError::Cancelled)));
+    }
+
+    #[test]
+    fn frame_steps_repeat_or_skip_steps() {
+        assert_eq!(frame_steps(3, 30, 15), vec![0, 0, 1, 1, 2, 2, 3]);
+        assert_eq!(frame_steps(10, 10, 40), vec![0, 4, 8, 10]);
+        assert_eq!(frame_steps(0, 30, 30), vec![0]);
+    }
+
+    #[test]
+    fn wav_has_fixed_length_per_step() {
+        let numbers = datatypes::NumberVec::new(vec![3, 1, 2]);
+        let timeline = player::Timeline::record(&algos::BubbleSort, numbers);
+        let options = WavOptions {
+            sample_rate: 8000,
+            step_duration: Duration::from_millis(10),
+            settings: sound::SoundSettings::default(),
+        };
+        let render = || {
+            let mut wav = std::io::Cursor::new(vec![]);
+            write_wav(&mut wav, &timeline, &options).unwrap();
+            wav.into_inner()
+        };
+        let wav = render();
+        assert_eq!(wav, render());
+
+        let reader = hound::WavReader::new(&wav[..]).unwrap();
+        assert_eq!(reader.len() as usize, timeline.len() * 80);
+        assert!(reader.into_samples::<i16>().any(|s| s.unwrap() != 0));
+    }
+
+    #[test]
+    fn png_has_canvas_size() {
+        let numbers = datatypes::NumberVec::new(vec![1, 2]);
+        let canvas = Canvas::with_bars(&numbers, 12, 8, &Style::default());
+        let mut png = vec![];
+        write_png(&mut png, &canvas).unwrap();
+        let reader = png::Decoder::new(&png[..]).read_info().unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (12, 8));
+    }
+}
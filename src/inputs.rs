@@ -0,0 +1,162 @@
+//! Generators for the values the algorithms are run on
+//!
+//! All presets produce values between 1 and the requested size, so bars of
+//! different presets are scaled the same way.
+//!
+//! Inputs are generated from a seed with a ChaCha RNG, which yields the same
+//! values on every platform. Random indices are always drawn as `u32`, since
+//! sampling `usize` ranges differs between 32 and 64 bit targets.
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// Shape of the generated input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InputPreset {
+    /// Uniform random permutation
+    Random,
+    Sorted,
+    Reversed,
+    /// Sorted, then a few random pairs are swapped
+    NearlySorted,
+    /// Random permutation of only a handful of distinct values
+    FewUnique,
+    /// Several ascending runs
+    Sawtooth,
+    /// Ascending up to the middle, descending afterwards
+    OrganPipe,
+    /// Random values where most appear multiple times
+    ManyDuplicates,
+}
+
+/// Number of distinct values for `InputPreset::FewUnique`
+const FEW_UNIQUE_VALUES: usize = 5;
+/// Number of ascending runs for `InputPreset::Sawtooth`
+const SAWTOOTH_TEETH: usize = 4;
+
+impl InputPreset {
+    pub const ALL: [InputPreset; 8] = [
+        InputPreset::Random,
+        InputPreset::Sorted,
+        InputPreset::Reversed,
+        InputPreset::NearlySorted,
+        InputPreset::FewUnique,
+        InputPreset::Sawtooth,
+        InputPreset::OrganPipe,
+        InputPreset::ManyDuplicates,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputPreset::Random => "Random",
+            InputPreset::Sorted => "Sorted",
+            InputPreset::Reversed => "Reversed",
+            InputPreset::NearlySorted => "Nearly sorted",
+            InputPreset::FewUnique => "Few unique",
+            InputPreset::Sawtooth => "Sawtooth",
+            InputPreset::OrganPipe => "Organ pipe",
+            InputPreset::ManyDuplicates => "Many duplicates",
+        }
+    }
+
+    /// Generate `size` values of this shape, the same `seed` always yields the
+    /// same values
+    pub fn generate(&self, size: usize, seed: u64) -> Vec<u32> {
+        let rng = &mut ChaCha8Rng::seed_from_u64(seed);
+        let n = size as u32;
+        match self {
+            InputPreset::Random => {
+                let mut values: Vec<u32> = (1..=n).collect();
+                values.shuffle(rng);
+                values
+            }
+            InputPreset::Sorted => (1..=n).collect(),
+            InputPreset::Reversed => (1..=n).rev().collect(),
+            InputPreset::NearlySorted => {
+                let mut values: Vec<u32> = (1..=n).collect();
+                // swap about 5% of the values
+                for _ in 0..(size / 20).max(1) {
+                    if size > 1 {
+                        let (i, j) = (rng.gen_range(0..n), rng.gen_range(0..n));
+                        values.swap(i as usize, j as usize);
+                    }
+                }
+                values
+            }
+            InputPreset::FewUnique => {
+                let levels = FEW_UNIQUE_VALUES.min(size.max(1));
+                let mut values: Vec<u32> = (0..size)
+                    .map(|i| ((i * levels / size + 1) * size / levels) as u32)
+                    .collect();
+                values.shuffle(rng);
+                values
+            }
+            InputPreset::Sawtooth => {
+                let tooth = (size / SAWTOOTH_TEETH).max(1);
+                (0..size)
+                    .map(|i| ((i % tooth + 1) * size / tooth) as u32)
+                    .collect()
+            }
+            InputPreset::OrganPipe => (0..size)
+                .map(|i| (2 * i.min(size - 1 - i) + 1) as u32)
+                .collect(),
+            InputPreset::ManyDuplicates => {
+                // every value is a multiple of 4, so each appears ~4 times
+                (0..size)
+                    .map(|_| (rng.gen_range(0..n.div_ceil(4)) + 1) * 4)
+                    .map(|v| v.min(n))
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_generate_requested_size_within_range() {
+        for preset in InputPreset::ALL {
+            for size in [1, 2, 5, 25, 1000] {
+                let values = preset.generate(size, 42);
+                assert_eq!(values.len(), size, "{}", preset.name());
+                assert!(
+                    values.iter().all(|v| (1..=size as u32).contains(v)),
+                    "{}: {values:?}",
+                    preset.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn permutation_presets_keep_all_values() {
+        for preset in [
+            InputPreset::Random,
+            InputPreset::Sorted,
+            InputPreset::Reversed,
+            InputPreset::NearlySorted,
+        ] {
+            let mut values = preset.generate(100, 42);
+            values.sort();
+            assert_eq!(values, (1..=100).collect::<Vec<u32>>(), "{}", preset.name());
+        }
+    }
+
+    #[test]
+    fn same_seed_yields_same_input() {
+        for preset in InputPreset::ALL {
+            assert_eq!(preset.generate(50, 7), preset.generate(50, 7));
+        }
+        assert_ne!(
+            InputPreset::Random.generate(50, 7),
+            InputPreset::Random.generate(50, 8)
+        );
+        // guards against changes of the RNG or the shuffling algorithm
+        assert_eq!(
+            InputPreset::Random.generate(10, 7),
+            vec![6, 5, 3, 1, 4, 7, 8, 9, 10, 2]
+        );
+    }
+}
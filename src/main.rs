@@ -1,32 +1,63 @@
+use clap::Parser;
 use eframe::egui;
+use rand::prelude::*;
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
 mod algos;
+mod cli;
 mod datatypes;
+mod export;
+mod font;
+mod inputs;
+mod names;
+mod player;
+mod render;
+mod settings;
+mod shortcuts;
+mod sound;
+mod trace;
 
-struct VerticalBarWidget {
-    height: u8,
-    width: u8,
-    color: egui::Color32,
+/// Space reserved for the controls left of the bars in each panel
+const CONTROLS_WIDTH: f32 = 230.;
+const PSEUDOCODE_WIDTH: f32 = 200.;
+const BARS_HEIGHT: f32 = 250.;
+const MIN_ARRAY_SIZE: usize = 5;
+/// Bubble sort records millions of operations on larger arrays
+const MAX_ARRAY_SIZE: usize = 2000;
+const MAX_COLUMNS: usize = 6;
+/// Upper bound for randomly picked seeds, small ones are easier to type
+const MAX_RANDOM_SEED: u64 = 1_000_000;
+/// GIFs exported from the UI skip steps to stay below this many frames
+const MAX_EXPORTED_FRAMES: usize = 500;
+/// Frame sequences exported from the UI play more steps per second to stay
+/// below this many frames, 5 minutes at 30 fps
+const MAX_EXPORTED_VIDEO_FRAMES: usize = 9000;
+/// Width and height of snapshots saved from a panel
+const SNAPSHOT_SIZE: (u32, u32) = (1280, 720);
+
+/// Draws all numbers as vertical bars, scaled to fill `size`
+struct BarChartWidget<'a> {
+    numbers: &'a datatypes::NumberVec,
+    size: egui::Vec2,
+    style: &'a render::Style,
 }
 
-impl VerticalBarWidget {
-    fn new(height: u8, color: egui::Color32) -> Self {
+impl<'a> BarChartWidget<'a> {
+    fn new(numbers: &'a datatypes::NumberVec, size: egui::Vec2, style: &'a render::Style) -> Self {
         Self {
-            height: height * 10,
-            width: 15,
-            color,
+            numbers,
+            size,
+            style,
         }
     }
 }
 
-impl egui::Widget for &mut VerticalBarWidget {
+impl egui::Widget for &mut BarChartWidget<'_> {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
-        let size = egui::vec2(self.width as f32, self.height as f32);
-        let (rect, response) = ui.allocate_at_least(
-            size,
+        let (rect, response) = ui.allocate_exact_size(
+            self.size,
             egui::Sense {
                 click: false,
                 drag: false,
@@ -34,44 +65,949 @@ impl egui::Widget for &mut VerticalBarWidget {
             },
         );
         let painter = ui.painter();
-        painter.rect_filled(rect, egui::Rounding::none(), self.color);
+        for (bar, color) in render::bars(self.numbers, rect, self.style) {
+            painter.rect_filled(bar, egui::Rounding::none(), color);
+        }
         response
     }
 }
 
+/// App-wide settings a panel needs to draw itself and to handle its buttons
+struct PanelContext<'a> {
+    array_size: usize,
+    bars_width: f32,
+    animation_delay: &'a Arc<AtomicU8>,
+    frame_options: &'a export::FrameOptions,
+    style: &'a render::Style,
+}
+
 /// Owns the number vector that's manipulated by the sorting algorithm
 struct AlgoVisualizer {
+    algorithm: &'static dyn algos::SortAlgorithm,
+    /// Shape of newly generated inputs
+    preset: inputs::InputPreset,
+    /// Seed the current input was generated with
+    seed: u64,
     numbers: Arc<Mutex<datatypes::NumberVec>>,
-    stop_flag: Arc<AtomicBool>,
+    controls: Arc<player::Controls>,
+    timeline: Arc<Mutex<player::Timeline>>,
     thread: Option<thread::JoinHandle<()>>,
+    /// Set while `thread` records its run. Whoever clears it first decides
+    /// whether the run is played or dropped, see `stop`.
+    recording: Arc<AtomicBool>,
+    /// Start of the race this visualizer takes part in
+    race: Option<Arc<player::StartGate>>,
+    /// Result of the last export, shown below the panel
+    export_status: Arc<Mutex<String>>,
+    /// Thread writing the last export, which may still be running
+    export_thread: Option<thread::JoinHandle<()>>,
+    /// Set to `true` to cancel the running export
+    cancel_export: Arc<AtomicBool>,
+    /// Mixer to play the compared and swapped values on, if sound works
+    mixer: Option<Arc<Mutex<sound::Mixer>>>,
+    muted: Arc<AtomicBool>,
 }
 
-impl Default for AlgoVisualizer {
-    fn default() -> Self {
+impl AlgoVisualizer {
+    /// Create a visualizer for `size` numbers in reversed order
+    fn new(
+        algorithm: &'static dyn algos::SortAlgorithm,
+        size: usize,
+        mixer: Option<Arc<Mutex<sound::Mixer>>>,
+    ) -> Self {
+        let (preset, seed) = (inputs::InputPreset::Reversed, 0);
         Self {
+            algorithm,
+            preset,
+            seed,
             numbers: Arc::new(Mutex::new(datatypes::NumberVec::new(
-                (1..=25).rev().collect(),
+                preset.generate(size, seed),
             ))),
-            stop_flag: Arc::new(AtomicBool::new(false)),
+            controls: Arc::new(player::Controls::default()),
+            timeline: Arc::new(Mutex::new(player::Timeline::default())),
             thread: None,
+            recording: Arc::new(AtomicBool::new(false)),
+            race: None,
+            export_status: Arc::new(Mutex::new(String::new())),
+            export_thread: None,
+            cancel_export: Arc::new(AtomicBool::new(false)),
+            mixer,
+            muted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Stop the thread working on the numbers, if there is one.
+    ///
+    /// Recording can't be interrupted, so a thread that is still recording is
+    /// left to finish on its own and drops its run instead of playing it.
+    fn stop(&mut self) {
+        if let Some(handle) = self.thread.take() {
+            if self.recording.swap(false, Ordering::AcqRel) {
+                return;
+            }
+            self.controls.stop.store(true, Ordering::Relaxed);
+            handle.join().unwrap();
+        }
+    }
+
+    /// Replace the numbers with `values`, discarding the recorded run
+    fn reset(&mut self, values: Vec<u32>) {
+        self.stop();
+        self.race = None;
+        *self.timeline.lock().unwrap() = player::Timeline::default();
+        *self.numbers.lock().unwrap() = datatypes::NumberVec::new(values);
+    }
+
+    /// Replace the numbers with the input of the selected preset and seed
+    fn generate_input(&mut self, size: usize) {
+        self.reset(self.preset.generate(size, self.seed));
+    }
+
+    /// Pick a new random seed and generate its input
+    fn new_input(&mut self, size: usize) {
+        self.seed = thread_rng().gen_range(0..MAX_RANDOM_SEED);
+        self.generate_input(size);
+    }
+
+    /// Record a run of the selected algorithm and start playing it.
+    ///
+    /// With a `race` gate, playback only starts once all other racers are
+    /// done recording.
+    fn start(
+        &mut self,
+        animation_delay: &Arc<AtomicU8>,
+        ctx: &egui::Context,
+        race: Option<Arc<player::StartGate>>,
+    ) {
+        self.stop();
+        self.race = race.clone();
+        *self.timeline.lock().unwrap() = player::Timeline::default();
+        let (algorithm, controls, numbers, timeline, delay, context) = (
+            self.algorithm,
+            Arc::clone(&self.controls),
+            Arc::clone(&self.numbers),
+            Arc::clone(&self.timeline),
+            Arc::clone(animation_delay),
+            ctx.clone(),
+        );
+        let (mixer, muted) = (self.mixer.clone(), Arc::clone(&self.muted));
+        controls.stop.store(false, Ordering::Relaxed);
+        // a fresh flag, an earlier thread may still hold the previous one
+        self.recording = Arc::new(AtomicBool::new(true));
+        let recording = Arc::clone(&self.recording);
+        self.thread = Some(thread::spawn(move || {
+            let initial = {
+                let mut nums = numbers.lock().unwrap();
+                nums.clear_run_state();
+                nums.clone()
+            };
+            let recorded = player::Timeline::record(algorithm, initial);
+            if !recording.swap(false, Ordering::AcqRel) {
+                // stopped while recording
+                return;
+            }
+            *timeline.lock().unwrap() = recorded;
+            if let Some(gate) = race {
+                if !gate.wait(&controls) {
+                    return;
+                }
+            }
+            player::play(timeline, numbers, delay, &context, controls, |op, nums| {
+                let Some(mixer) = &mixer else {
+                    return;
+                };
+                if muted.load(Ordering::Relaxed) {
+                    return;
+                }
+                let mut mixer = mixer.lock().unwrap();
+                for frequency in sound::step_frequencies(op, nums) {
+                    mixer.play(frequency, sound::TONE_DURATION);
+                }
+            })
+        }));
+    }
+
+    /// Controls, bars, timeline and counters of this visualizer
+    fn ui(&mut self, id: usize, ui: &mut egui::Ui, panel: &PanelContext) {
+        let ctx = &ui.ctx().clone();
+        let (array_size, bars_width) = (panel.array_size, panel.bars_width);
+        ui.allocate_ui_with_layout(
+            egui::vec2(CONTROLS_WIDTH + bars_width + PSEUDOCODE_WIDTH, BARS_HEIGHT),
+            egui::Layout::left_to_right(egui::Align::Center),
+            |ui| {
+                ui.vertical(|ui| {
+                    ui.set_width(CONTROLS_WIDTH);
+                    egui::ComboBox::from_id_source(("algorithm", id))
+                        .selected_text(self.algorithm.name())
+                        .show_ui(ui, |ui| {
+                            for algorithm in algos::ALGORITHMS {
+                                let selected = algorithm.name() == self.algorithm.name();
+                                if ui
+                                    .selectable_label(selected, algorithm.name())
+                                    .on_hover_text(algorithm.description())
+                                    .clicked()
+                                {
+                                    self.algorithm = *algorithm;
+                                }
+                            }
+                        })
+                        .response
+                        .on_hover_text(self.algorithm.description());
+                    ui.horizontal(|ui| {
+                        preset_combo_box(ui, ("preset", id), &mut self.preset);
+                        if ui.add(egui::Button::new("New input")).clicked() {
+                            self.new_input(array_size);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Seed");
+                        if ui.add(egui::DragValue::new(&mut self.seed)).changed() {
+                            self.generate_input(array_size);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.add(egui::Button::new("Sort")).clicked() {
+                            self.start(panel.animation_delay, ctx, None);
+                        }
+                        let mut muted = self.muted.load(Ordering::Relaxed);
+                        if ui
+                            .add_enabled(
+                                self.mixer.is_some(),
+                                egui::Checkbox::new(&mut muted, "Mute"),
+                            )
+                            .changed()
+                        {
+                            self.muted.store(muted, Ordering::Relaxed);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.add(egui::Button::new("Step back")).clicked() {
+                            self.step_back();
+                        }
+                        let label = if self.controls.pause.load(Ordering::Relaxed) {
+                            "Resume"
+                        } else {
+                            "Pause"
+                        };
+                        if ui.add(egui::Button::new(label)).clicked() {
+                            self.controls.toggle_pause();
+                        }
+                        if ui.add(egui::Button::new("Next step")).clicked() {
+                            self.controls.step_once();
+                        }
+                    });
+                    let exportable = self.can_export();
+                    if ui
+                        .add_enabled(exportable, egui::Button::new("Export GIF"))
+                        .on_hover_text("Save the recorded run as a GIF in the working directory")
+                        .clicked()
+                    {
+                        self.export_gif(panel.style);
+                    }
+                    if ui
+                        .add_enabled(exportable, egui::Button::new("Export frames"))
+                        .on_hover_text(
+                            "Save the recorded run as numbered PNG frames in the working directory",
+                        )
+                        .clicked()
+                    {
+                        self.export_frames(panel.frame_options, panel.style);
+                    }
+                    if ui
+                        .button("Save snapshot")
+                        .on_hover_text("Save the bars as PNG and SVG in the working directory")
+                        .clicked()
+                    {
+                        self.save_snapshot(panel.style);
+                    }
+                });
+                // the player locks the timeline before the numbers, so look
+                // at the timeline first
+                let algorithm = self.recorded_algorithm();
+                let nums = self.numbers.lock().unwrap();
+                ui.add(&mut BarChartWidget::new(
+                    &nums,
+                    egui::vec2(bars_width, BARS_HEIGHT),
+                    panel.style,
+                ));
+                self.pseudocode_ui(ui, algorithm, nums.line, panel.style);
+            },
+        );
+        self.timeline_ui(ui);
+        self.counters_ui(ui);
+        ui.horizontal(|ui| {
+            let status = self.export_status.lock().unwrap();
+            if !status.is_empty() {
+                ui.label(status.as_str());
+            }
+            if self.is_exporting() && ui.button("Cancel").clicked() {
+                self.cancel_export.store(true, Ordering::Relaxed);
+            }
+        });
+    }
+
+    /// Whether a run was recorded that can be played or exported
+    fn is_recorded(&self) -> bool {
+        !self.timeline.lock().unwrap().is_empty()
+    }
+
+    fn is_exporting(&self) -> bool {
+        self.export_thread
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
+
+    /// Whether the recorded run can be exported, one export at a time
+    fn can_export(&self) -> bool {
+        self.is_recorded() && !self.is_exporting()
+    }
+
+    /// Write an export to `path` with `save` in the background, showing the
+    /// message `save` returns once it's done
+    fn spawn_export(
+        &mut self,
+        path: std::path::PathBuf,
+        save: impl FnOnce(&std::path::Path, &AtomicBool) -> String + Send + 'static,
+    ) {
+        let status = Arc::clone(&self.export_status);
+        let cancel = Arc::clone(&self.cancel_export);
+        cancel.store(false, Ordering::Relaxed);
+        *status.lock().unwrap() = format!("Exporting {}…", path.display());
+        self.export_thread = Some(thread::spawn(move || {
+            let message = save(&path, &cancel);
+            *status.lock().unwrap() = message;
+        }));
+    }
+
+    /// Algorithm of the recorded run, or the selected one if there is none.
+    ///
+    /// The selection can change while a run is playing, but highlighted lines,
+    /// titles and exports have to match the run.
+    fn recorded_algorithm(&self) -> &'static dyn algos::SortAlgorithm {
+        self.timeline
+            .lock()
+            .unwrap()
+            .algorithm()
+            .unwrap_or(self.algorithm)
+    }
+
+    /// Pseudocode of `algorithm` with the line it's at highlighted
+    fn pseudocode_ui(
+        &self,
+        ui: &mut egui::Ui,
+        algorithm: &dyn algos::SortAlgorithm,
+        current: Option<usize>,
+        style: &render::Style,
+    ) {
+        ui.vertical(|ui| {
+            ui.set_width(PSEUDOCODE_WIDTH);
+            ui.spacing_mut().item_spacing.y = 0.;
+            for (i, line) in algorithm.pseudocode().iter().enumerate() {
+                let mut text = egui::RichText::new(*line).monospace();
+                if current == Some(i) {
+                    text = text
+                        .background_color(style.highlight(datatypes::Highlight::Primary))
+                        .color(egui::Color32::BLACK);
+                }
+                ui.label(text);
+            }
+        });
+    }
+
+    /// Render the recorded run as a GIF into the working directory.
+    ///
+    /// Runs in the background, since encoding can take a while.
+    fn export_gif(&mut self, style: &render::Style) {
+        let timeline = self.timeline.lock().unwrap().clone();
+        let path = std::path::PathBuf::from(format!(
+            "visu-{}-{}.gif",
+            names::normalize(self.recorded_algorithm().name()),
+            self.seed
+        ));
+        let options = export::GifOptions {
+            width: 640,
+            height: 360,
+            every: timeline.len().div_ceil(MAX_EXPORTED_FRAMES).max(1),
+            frame_delay_ms: 50,
+            style: *style,
+        };
+        self.spawn_export(path, move |path, cancel| {
+            match export::save_gif(path, &timeline, &options, cancel) {
+                Ok(()) => format!("Saved {}", path.display()),
+                Err(err) => err.to_string(),
+            }
+        });
+    }
+
+    /// Render the recorded run as numbered PNG frames into a new directory in
+    /// the working directory, in the background like `export_gif`.
+    ///
+    /// Long runs are played faster than `options.steps_per_second`, so there
+    /// are at most `MAX_EXPORTED_VIDEO_FRAMES` frames.
+    fn export_frames(&mut self, options: &export::FrameOptions, style: &render::Style) {
+        let timeline = self.timeline.lock().unwrap().clone();
+        let dir = std::path::PathBuf::from(format!(
+            "visu-{}-{}-frames",
+            names::normalize(self.recorded_algorithm().name()),
+            self.seed
+        ));
+        let fastest = (timeline.len() as u64 * options.fps as u64)
+            .div_ceil(MAX_EXPORTED_VIDEO_FRAMES as u64) as u32;
+        let options = export::FrameOptions {
+            steps_per_second: options.steps_per_second.max(fastest),
+            style: *style,
+            ..options.clone()
+        };
+        let title = self.title();
+        self.spawn_export(dir, move |dir, cancel| {
+            match export::save_frames(dir, &timeline, &options, &title, cancel) {
+                Ok(frames) => format!(
+                    "Saved {frames} frames to {} at {} steps per second",
+                    dir.display(),
+                    options.steps_per_second
+                ),
+                Err(err) => err.to_string(),
+            }
+        });
+    }
+
+    /// Algorithm, preset and seed, shown above exported bars
+    fn title(&self) -> String {
+        format!(
+            "{} - {}, seed {}",
+            self.recorded_algorithm().name(),
+            self.preset.name(),
+            self.seed
+        )
+    }
+
+    /// Write the current bars and counters as PNG and SVG into the working
+    /// directory
+    fn save_snapshot(&self, style: &render::Style) {
+        let step = self.timeline.lock().unwrap().position();
+        let path = std::path::PathBuf::from(format!(
+            "visu-{}-{}-step{step}",
+            names::normalize(self.recorded_algorithm().name()),
+            self.seed
+        ));
+        let mut lines = vec![self.title()];
+        lines.extend(self.counters_text());
+        let numbers = self.numbers.lock().unwrap();
+        let (width, height) = SNAPSHOT_SIZE;
+        *self.export_status.lock().unwrap() =
+            match export::save_snapshot(&path, &numbers, width, height, &lines, style) {
+                Ok(()) => format!("Saved {}.png and .svg", path.display()),
+                Err(err) => err.to_string(),
+            };
+    }
+
+    /// Pause and move the timeline back by one step
+    fn step_back(&self) {
+        self.controls.pause.store(true, Ordering::Relaxed);
+        let mut timeline = self.timeline.lock().unwrap();
+        if timeline.position() > 0 {
+            let step = timeline.position() - 1;
+            timeline.seek(step, &mut self.numbers.lock().unwrap());
+        }
+    }
+
+    /// Slider to move to any step of the recorded run
+    fn timeline_ui(&self, ui: &mut egui::Ui) {
+        let mut timeline = self.timeline.lock().unwrap();
+        if timeline.is_empty() {
+            return;
+        }
+        let mut step = timeline.position();
+        let total = timeline.len();
+        let slider = egui::Slider::new(&mut step, 0..=total).text(format!(
+            "of {total} steps ({} operations)",
+            timeline.operations().len()
+        ));
+        if ui.add(slider).changed() {
+            timeline.seek(step, &mut self.numbers.lock().unwrap());
+        }
+    }
+
+    /// Operation counts up to the current step, or the totals once sorted
+    fn counters_ui(&self, ui: &mut egui::Ui) {
+        if let Some(text) = self.counters_text() {
+            ui.label(text);
+        }
+    }
+
+    fn counters_text(&self) -> Option<String> {
+        let timeline = self.timeline.lock().unwrap();
+        if timeline.is_empty() {
+            return None;
+        }
+        let counters = timeline.counters();
+        Some(
+            if timeline.at_end() && self.numbers.lock().unwrap().is_sorted() {
+                format!("Sorted after {} steps: {counters}", counters.steps)
+            } else {
+                format!("Step {}: {counters}", counters.steps)
+            },
+        )
+    }
+}
+
+/// Changes to the list of panels, requested from within the panel loop or by
+/// a shortcut
+enum PanelAction {
+    MoveBack(usize),
+    MoveForward(usize),
+    Remove(usize),
+}
+
+/// Swatches of all colors used for bars with their meaning
+fn legend_ui(ui: &mut egui::Ui, style: &render::Style) {
+    ui.horizontal(|ui| {
+        for (colors, meaning) in style.legend() {
+            let size = egui::vec2(12. * colors.len() as f32, 12.);
+            let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+            for (i, color) in colors.iter().enumerate() {
+                let swatch = egui::Rect::from_min_size(
+                    rect.min + egui::vec2(12. * i as f32, 0.),
+                    egui::vec2(12., 12.),
+                );
+                ui.painter()
+                    .rect_filled(swatch, egui::Rounding::none(), *color);
+            }
+            ui.label(meaning);
         }
+    });
+}
+
+/// Dropdown to select one of `inputs::InputPreset::ALL`
+fn preset_combo_box(ui: &mut egui::Ui, id: impl std::hash::Hash, preset: &mut inputs::InputPreset) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(preset.name())
+        .show_ui(ui, |ui| {
+            for option in inputs::InputPreset::ALL {
+                ui.selectable_value(preset, option, option.name());
+            }
+        });
+}
+
+impl Drop for AlgoVisualizer {
+    fn drop(&mut self) {
+        self.stop();
     }
 }
 
 struct VisuApp {
     visualizers: Vec<AlgoVisualizer>,
+    /// Index of the panel most keyboard shortcuts act on
+    focused: usize,
+    /// Whether the list of keyboard shortcuts is shown
+    show_help: bool,
+    /// Number of panels shown next to each other
+    columns: usize,
+    /// Number of elements in every panel
+    array_size: usize,
+    /// Input preset applied to all panels at once
+    preset: inputs::InputPreset,
+    /// Seed applied to all panels at once
+    seed: u64,
+    /// Whether all panels get the same seed, or each one a different one
+    same_input: bool,
     animation_delay_ms: Arc<AtomicU8>,
+    /// Settings for frame sequences exported from any panel
+    frame_options: export::FrameOptions,
+    /// Colors of all panels and exports
+    style: render::Style,
+    sound_settings: sound::SoundSettings,
+    /// Audio device all panels play their tones on
+    sound: Result<sound::Output, sound::SoundError>,
 }
 
 impl VisuApp {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        // Customize egui here with cc.egui_ctx.set_fonts and cc.egui_ctx.set_visuals.
-        // Restore app state using cc.storage (requires the "persistence" feature).
-        // Use the cc.gl (a glow::Context) to create graphics shaders and buffers that you can use
-        // for e.g. egui::PaintCallback.
+    /// Volume and waveform of the tones of all panels
+    fn sound_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let output = match &self.sound {
+                Ok(output) => output,
+                Err(err) => {
+                    ui.label(format!("Sound: {err}"))
+                        .on_hover_text("Build with `--features audio` to hear the algorithms");
+                    return;
+                }
+            };
+            let settings = &mut self.sound_settings;
+            ui.add(egui::Slider::new(&mut settings.volume, 0.0..=1.0).text("Volume"));
+            egui::ComboBox::from_label("Waveform")
+                .selected_text(settings.waveform.name())
+                .show_ui(ui, |ui| {
+                    for waveform in sound::Waveform::ALL {
+                        ui.selectable_value(&mut settings.waveform, waveform, waveform.name());
+                    }
+                });
+            output.mixer().lock().unwrap().settings = *settings;
+        });
+    }
+
+    /// Palette, theme, highlight colors and a legend explaining them
+    fn style_ui(&mut self, ui: &mut egui::Ui) {
+        let before = self.style;
+        let style = &mut self.style;
+        egui::CollapsingHeader::new("Colors").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_label("Palette")
+                    .selected_text(style.palette.name())
+                    .show_ui(ui, |ui| {
+                        for palette in render::Palette::ALL {
+                            let label = if palette.colorblind_safe() {
+                                format!("{} (colorblind safe)", palette.name())
+                            } else {
+                                palette.name().to_string()
+                            };
+                            if ui
+                                .selectable_label(style.palette == palette, label)
+                                .clicked()
+                            {
+                                // highlights have to stand out from the new palette
+                                *style = render::Style::new(palette, style.dark);
+                            }
+                        }
+                    });
+                ui.checkbox(&mut style.dark, "Dark mode");
+            });
+            ui.horizontal_wrapped(|ui| {
+                for highlight in datatypes::Highlight::ALL {
+                    ui.color_edit_button_srgba(style.highlight_mut(highlight));
+                    ui.label(highlight.name());
+                }
+                if ui.button("Reset highlights").clicked() {
+                    style.reset_highlights();
+                }
+            });
+        });
+        legend_ui(ui, style);
+        if self.style.dark != before.dark {
+            ui.ctx().set_visuals(self.style.visuals());
+        }
+    }
+
+    /// Settings used by "Export frames" of every panel
+    fn frame_options_ui(&mut self, ui: &mut egui::Ui) {
+        let options = &mut self.frame_options;
+        egui::CollapsingHeader::new("Frame export").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Resolution");
+                ui.add(egui::DragValue::new(&mut options.width).clamp_range(16..=7680));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut options.height).clamp_range(16..=4320));
+                ui.label("Frame rate");
+                ui.add(egui::DragValue::new(&mut options.fps).clamp_range(1..=240));
+                ui.label("Steps per second");
+                ui.add(egui::DragValue::new(&mut options.steps_per_second).clamp_range(1..=10000));
+                ui.checkbox(&mut options.overlay, "Overlay name and counters");
+            });
+        });
+    }
+
+    /// Restore the app from the settings saved on the last exit, if any
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let settings: settings::Settings = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        cc.egui_ctx.set_visuals(settings.style.visuals());
+        let sound_settings = sound::SoundSettings::default();
+        let sound = sound::Output::open(sound_settings);
+        let mixer = sound.as_ref().ok().map(|output| output.mixer());
+        // stay within the ranges of the sliders
+        let array_size = settings.array_size.clamp(MIN_ARRAY_SIZE, MAX_ARRAY_SIZE);
+        let visualizers = settings
+            .panels
+            .iter()
+            .map(|panel| {
+                let mut visualizer =
+                    AlgoVisualizer::new(panel.algorithm(), array_size, mixer.clone());
+                visualizer.preset = panel.preset;
+                visualizer.seed = panel.seed;
+                visualizer.generate_input(array_size);
+                visualizer
+            })
+            .collect();
         Self {
-            visualizers: vec![AlgoVisualizer::default(), AlgoVisualizer::default()],
-            animation_delay_ms: Arc::new(AtomicU8::new(10)),
+            visualizers,
+            focused: 0,
+            show_help: false,
+            columns: settings.columns.clamp(1, MAX_COLUMNS),
+            array_size,
+            preset: settings.preset,
+            seed: settings.seed,
+            same_input: settings.same_input,
+            animation_delay_ms: Arc::new(AtomicU8::new(settings.animation_delay_ms.min(100))),
+            frame_options: export::FrameOptions::default(),
+            style: settings.style,
+            sound_settings,
+            sound,
+        }
+    }
+
+    /// Current state of everything that is remembered between launches
+    fn settings(&self) -> settings::Settings {
+        settings::Settings {
+            animation_delay_ms: self.animation_delay_ms.load(Ordering::Acquire),
+            panels: self
+                .visualizers
+                .iter()
+                .map(|visualizer| settings::PanelSettings {
+                    algorithm: visualizer.algorithm.name().to_string(),
+                    preset: visualizer.preset,
+                    seed: visualizer.seed,
+                })
+                .collect(),
+            columns: self.columns,
+            array_size: self.array_size,
+            preset: self.preset,
+            seed: self.seed,
+            same_input: self.same_input,
+            style: self.style,
+        }
+    }
+
+    fn all_paused(&self) -> bool {
+        self.visualizers
+            .iter()
+            .all(|v| v.controls.pause.load(Ordering::Relaxed))
+    }
+
+    /// Resume all panels if all are paused, pause all otherwise
+    fn toggle_pause_all(&self) {
+        let all_paused = self.all_paused();
+        for visualizer in self.visualizers.iter() {
+            visualizer
+                .controls
+                .pause
+                .store(!all_paused, Ordering::Relaxed);
+        }
+    }
+
+    /// Add a panel at the end and focus it
+    fn add_panel(&mut self) {
+        let mixer = self.sound.as_ref().ok().map(|output| output.mixer());
+        self.visualizers.push(AlgoVisualizer::new(
+            algos::ALGORITHMS[0],
+            self.array_size,
+            mixer,
+        ));
+        self.focused = self.visualizers.len() - 1;
+    }
+
+    /// Pick a new random seed and generate inputs for all panels from it
+    fn new_input_for_all(&mut self) {
+        self.seed = thread_rng().gen_range(0..MAX_RANDOM_SEED);
+        self.generate_input_for_all();
+    }
+
+    /// Move or remove a panel. The focus stays on the moved panel.
+    fn apply_panel_action(&mut self, action: PanelAction) {
+        match action {
+            PanelAction::MoveBack(idx) => {
+                self.visualizers.swap(idx - 1, idx);
+                self.focused = idx - 1;
+            }
+            PanelAction::MoveForward(idx) => {
+                self.visualizers.swap(idx, idx + 1);
+                self.focused = idx + 1;
+            }
+            PanelAction::Remove(idx) => {
+                // dropping the visualizer stops its thread
+                self.visualizers.remove(idx);
+                self.focused = self.focused.min(self.visualizers.len().saturating_sub(1));
+            }
+        }
+    }
+
+    /// Change the animation speed by `delta` steps of the speed slider
+    fn change_speed(&self, delta: i8) {
+        let delay = self.animation_delay_ms.load(Ordering::Acquire);
+        let speed = delay_to_speed(&delay)
+            .saturating_add_signed(delta)
+            .clamp(1, 11);
+        self.animation_delay_ms
+            .store(speed_to_delay(&speed), Ordering::Release);
+    }
+
+    fn apply_shortcut(&mut self, action: shortcuts::Action, ctx: &egui::Context) {
+        use shortcuts::Action;
+
+        let (idx, count) = (self.focused, self.visualizers.len());
+        match action {
+            Action::NewInputForAll => self.new_input_for_all(),
+            Action::Race => self.start_race(ctx),
+            Action::TogglePauseForAll => self.toggle_pause_all(),
+            Action::NextStepForAll => {
+                for visualizer in self.visualizers.iter() {
+                    visualizer.controls.step_once();
+                }
+            }
+            Action::StepBackForAll => {
+                for visualizer in self.visualizers.iter() {
+                    visualizer.step_back();
+                }
+            }
+            Action::SpeedUp => self.change_speed(1),
+            Action::SlowDown => self.change_speed(-1),
+            Action::FocusNext if count > 0 => self.focused = (idx + 1) % count,
+            Action::FocusPrevious if count > 0 => self.focused = (idx + count - 1) % count,
+            Action::Focus(panel) if panel < count => self.focused = panel,
+            Action::MovePanelBack if idx > 0 && idx < count => {
+                self.apply_panel_action(PanelAction::MoveBack(idx))
+            }
+            Action::MovePanelForward if idx + 1 < count => {
+                self.apply_panel_action(PanelAction::MoveForward(idx))
+            }
+            Action::AddPanel => self.add_panel(),
+            Action::RemovePanel if idx < count => self.apply_panel_action(PanelAction::Remove(idx)),
+            Action::ResetHighlights => self.style.reset_highlights(),
+            Action::ToggleHelp => self.show_help = !self.show_help,
+            _ => {
+                // everything else acts on the focused panel
+                let Some(visualizer) = self.visualizers.get_mut(idx) else {
+                    return;
+                };
+                match action {
+                    Action::NewInput => visualizer.new_input(self.array_size),
+                    Action::Sort => visualizer.start(&self.animation_delay_ms, ctx, None),
+                    Action::Restart => {
+                        visualizer.generate_input(self.array_size);
+                        visualizer.start(&self.animation_delay_ms, ctx, None);
+                    }
+                    Action::TogglePause => visualizer.controls.toggle_pause(),
+                    Action::NextStep => visualizer.controls.step_once(),
+                    Action::StepBack => visualizer.step_back(),
+                    Action::ExportGif if visualizer.can_export() => {
+                        visualizer.export_gif(&self.style)
+                    }
+                    Action::ExportFrames if visualizer.can_export() => {
+                        visualizer.export_frames(&self.frame_options, &self.style)
+                    }
+                    Action::CancelExport => {
+                        visualizer.cancel_export.store(true, Ordering::Relaxed);
+                    }
+                    Action::SaveSnapshot => visualizer.save_snapshot(&self.style),
+                    Action::ToggleMute => {
+                        visualizer.muted.fetch_xor(true, Ordering::Relaxed);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Window listing all keyboard shortcuts
+    fn help_ui(&mut self, ctx: &egui::Context) {
+        egui::Window::new("Keyboard shortcuts")
+            .open(&mut self.show_help)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Shortcuts act on the focused panel, with Shift on all panels.");
+                egui::Grid::new("shortcuts").striped(true).show(ui, |ui| {
+                    for binding in shortcuts::BINDINGS {
+                        ui.monospace(shortcuts::format(&binding.shortcut));
+                        ui.label(binding.description);
+                        ui.end_row();
+                    }
+                    ui.monospace("1 - 9");
+                    ui.label("Focus panel with that number");
+                    ui.end_row();
+                });
+            });
+    }
+
+    /// Copy the input of the first panel to all others and start all of them
+    /// at the same instant
+    fn start_race(&mut self, ctx: &egui::Context) {
+        let Some(first) = self.visualizers.first() else {
+            return;
+        };
+        let (preset, seed) = (first.preset, first.seed);
+        let gate = Arc::new(player::StartGate::new(self.visualizers.len()));
+        for visualizer in self.visualizers.iter_mut() {
+            visualizer.preset = preset;
+            visualizer.seed = seed;
+            visualizer.generate_input(self.array_size);
+            visualizer.start(&self.animation_delay_ms, ctx, Some(Arc::clone(&gate)));
+        }
+    }
+
+    /// Finishing order of the current race with time and operation counts
+    fn race_ui(&mut self, ctx: &egui::Context) {
+        let mut results = vec![];
+        for (idx, visualizer) in self.visualizers.iter().enumerate() {
+            let Some(gate) = &visualizer.race else {
+                continue;
+            };
+            let timeline = visualizer.timeline.lock().unwrap();
+            let time = gate
+                .started()
+                .zip(timeline.finished_at())
+                .and_then(|(started, finished)| finished.checked_duration_since(started));
+            let sorted = visualizer.numbers.lock().unwrap().is_sorted();
+            results.push((
+                idx,
+                timeline.algorithm().unwrap_or(visualizer.algorithm).name(),
+                time,
+                timeline.total_counters(),
+                sorted,
+            ));
+        }
+        if results.is_empty() {
+            return;
+        }
+        // unfinished racers go last
+        results.sort_by_key(|(_, _, time, _, _)| time.unwrap_or(std::time::Duration::MAX));
+
+        let mut open = true;
+        egui::Window::new("Race").open(&mut open).show(ctx, |ui| {
+            egui::Grid::new("race_results")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (place, (idx, name, time, counters, sorted)) in results.iter().enumerate() {
+                        match time {
+                            Some(time) => {
+                                ui.label(format!("{}.", place + 1));
+                                ui.label(format!("{name} (panel {})", idx + 1));
+                                ui.label(format!("{:.2} s", time.as_secs_f32()));
+                                if *sorted {
+                                    ui.label(format!("{counters}"));
+                                } else {
+                                    ui.label("not sorted");
+                                }
+                            }
+                            None => {
+                                ui.label("");
+                                ui.label(format!("{name} (panel {})", idx + 1));
+                                ui.label("running");
+                                ui.label("");
+                            }
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+        if !open {
+            for visualizer in self.visualizers.iter_mut() {
+                visualizer.race = None;
+            }
+        }
+    }
+
+    /// Generate the selected preset for all panels, either with the same seed
+    /// or with one seed per panel, counting up from `self.seed`
+    fn generate_input_for_all(&mut self) {
+        for (i, visualizer) in self.visualizers.iter_mut().enumerate() {
+            visualizer.preset = self.preset;
+            visualizer.seed = if self.same_input {
+                self.seed
+            } else {
+                self.seed.wrapping_add(i as u64)
+            };
+            visualizer.generate_input(self.array_size);
         }
     }
 }
@@ -85,171 +1021,152 @@ fn speed_to_delay(v: &u8) -> u8 {
 }
 
 impl eframe::App for VisuApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.settings());
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        for action in shortcuts::pressed(ctx) {
+            self.apply_shortcut(action, ctx);
+        }
         egui::TopBottomPanel::top("title_panel").show(ctx, |ui| {
             ui.heading("Welcome to VISU!");
+            ui.horizontal(|ui| {
+                let label = if self.all_paused() {
+                    "Resume all"
+                } else {
+                    "Pause all"
+                };
+                if ui.add(egui::Button::new(label)).clicked() {
+                    self.toggle_pause_all();
+                }
+                if ui.add(egui::Button::new("Step back (all)")).clicked() {
+                    for visualizer in self.visualizers.iter() {
+                        visualizer.step_back();
+                    }
+                }
+                if ui.add(egui::Button::new("Next step (all)")).clicked() {
+                    for visualizer in self.visualizers.iter() {
+                        visualizer.controls.step_once();
+                    }
+                }
+                if ui.add(egui::Button::new("Keyboard shortcuts")).clicked() {
+                    self.show_help = !self.show_help;
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new("Add panel")).clicked() {
+                    self.add_panel();
+                }
+                ui.add(egui::Slider::new(&mut self.columns, 1..=MAX_COLUMNS).text("Columns"));
+                preset_combo_box(ui, "preset_all", &mut self.preset);
+                if ui.add(egui::Button::new("New input for all")).clicked() {
+                    self.new_input_for_all();
+                }
+                ui.label("Seed");
+                if ui.add(egui::DragValue::new(&mut self.seed)).changed() {
+                    self.generate_input_for_all();
+                }
+                ui.checkbox(&mut self.same_input, "Same input for every panel");
+                if ui
+                    .add(egui::Button::new("Race"))
+                    .on_hover_text(
+                        "Copy the input of the first panel to all panels and start them at once",
+                    )
+                    .clicked()
+                {
+                    self.start_race(ctx);
+                }
+                let size_slider =
+                    egui::Slider::new(&mut self.array_size, MIN_ARRAY_SIZE..=MAX_ARRAY_SIZE)
+                        .logarithmic(true)
+                        .text("Array size");
+                if ui.add(size_slider).changed() {
+                    for visualizer in self.visualizers.iter_mut() {
+                        visualizer.generate_input(self.array_size);
+                    }
+                }
+                // Animation speed slider
+                let animation_delay = Arc::clone(&self.animation_delay_ms);
+                let mut speed = delay_to_speed(&animation_delay.load(Ordering::Acquire));
+                ui.add(egui::Slider::new(&mut speed, 1..=11).text("Animation speed"));
+                animation_delay.store(speed_to_delay(&speed), Ordering::Release);
+            });
+            self.sound_ui(ui);
+            self.style_ui(ui);
+            self.frame_options_ui(ui);
         });
+        self.race_ui(ctx);
+        self.help_ui(ctx);
         egui::CentralPanel::default().show(ctx, |ui| {
-            ui.vertical(|ui| {
-                // TODO: LOOP THIS!
-                ui.allocate_ui_with_layout(
-                    egui::vec2(350., 250.),
-                    egui::Layout::left_to_right(egui::Align::Center),
-                    |ui| {
-                        let visualizer: &mut AlgoVisualizer = &mut self.visualizers[0];
-                        ui.vertical(|ui| {
-                            if ui.add(egui::Button::new("Shuffle numbers")).clicked() {
-                                if let Some(handle) = visualizer.thread.take() {
-                                    let flag = Arc::clone(&visualizer.stop_flag);
-                                    flag.store(true, Ordering::Relaxed);
-                                    handle.join().unwrap();
-                                }
-                                let numbers = Arc::clone(&visualizer.numbers);
-                                visualizer.thread =
-                                    Some(thread::spawn(move || algos::shuffle(numbers)));
-                            } else if ui.add(egui::Button::new("Bubble Sort")).clicked() {
-                                if let Some(handle) = visualizer.thread.take() {
-                                    let flag = Arc::clone(&visualizer.stop_flag);
-                                    flag.store(true, Ordering::Relaxed);
-                                    handle.join().unwrap();
-                                }
-                                let (flag, numbers, delay, context) = (
-                                    Arc::clone(&visualizer.stop_flag),
-                                    Arc::clone(&visualizer.numbers),
-                                    Arc::clone(&self.animation_delay_ms),
-                                    ctx.clone(),
-                                );
-                                flag.store(false, Ordering::Relaxed);
-                                visualizer.thread = Some(thread::spawn(move || {
-                                    algos::bubblesort(numbers, delay, &context, flag)
-                                }));
-                            } else if ui.add(egui::Button::new("Quick Sort")).clicked() {
-                                if let Some(handle) = visualizer.thread.take() {
-                                    let flag = Arc::clone(&visualizer.stop_flag);
-                                    flag.store(true, Ordering::Relaxed);
-                                    handle.join().unwrap();
-                                }
-                                let (flag, numbers, highest_index, delay, context) = (
-                                    Arc::clone(&visualizer.stop_flag),
-                                    Arc::clone(&visualizer.numbers),
-                                    Arc::clone(&visualizer.numbers).lock().unwrap().values.len()
-                                        - 1,
-                                    Arc::clone(&self.animation_delay_ms),
-                                    ctx.clone(),
-                                );
-                                flag.store(false, Ordering::Relaxed);
-                                visualizer.thread = Some(thread::spawn(move || {
-                                    algos::quicksort(
-                                        numbers,
-                                        0,
-                                        highest_index,
-                                        &delay,
-                                        &context,
-                                        &flag,
-                                    )
-                                }));
-                            }
-                        });
-                        ui.with_layout(egui::Layout::left_to_right(egui::Align::BOTTOM), |ui| {
-                            let numbers = Arc::clone(&visualizer.numbers);
-                            for num in numbers.lock().unwrap().values.iter() {
-                                let col = match num.highlight {
-                                    datatypes::Highlight::None => num.color,
-                                    datatypes::Highlight::Primary => egui::Color32::KHAKI,
-                                    datatypes::Highlight::Secondary => {
-                                        num.color.linear_multiply(0.5)
+            let mut action = None;
+            let spacing = 20.;
+            let bars_width = ((ui.available_width() - spacing * (self.columns - 1) as f32)
+                / self.columns as f32
+                - CONTROLS_WIDTH
+                - PSEUDOCODE_WIDTH)
+                .max(100.);
+            let panel = PanelContext {
+                array_size: self.array_size,
+                bars_width,
+                animation_delay: &self.animation_delay_ms,
+                frame_options: &self.frame_options,
+                style: &self.style,
+            };
+            egui::ScrollArea::both().show(ui, |ui| {
+                egui::Grid::new("visualizers")
+                    .spacing(egui::vec2(spacing, spacing))
+                    .show(ui, |ui| {
+                        let count = self.visualizers.len();
+                        for (idx, visualizer) in self.visualizers.iter_mut().enumerate() {
+                            ui.vertical(|ui| {
+                                ui.horizontal(|ui| {
+                                    let label = format!("Panel {}", idx + 1);
+                                    if ui
+                                        .selectable_label(idx == self.focused, label)
+                                        .on_hover_text("Focus this panel for keyboard shortcuts")
+                                        .clicked()
+                                    {
+                                        self.focused = idx;
                                     }
-                                };
-                                ui.add(&mut VerticalBarWidget::new(num.value, col));
-                            }
-                        })
-                    },
-                );
-                ui.add_space(20.);
-                ui.allocate_ui_with_layout(
-                    egui::vec2(350., 250.),
-                    egui::Layout::left_to_right(egui::Align::Center),
-                    |ui| {
-                        let visualizer: &mut AlgoVisualizer = &mut self.visualizers[1];
-                        ui.vertical(|ui| {
-                            if ui.add(egui::Button::new("Shuffle numbers")).clicked() {
-                                if let Some(handle) = visualizer.thread.take() {
-                                    let flag = Arc::clone(&visualizer.stop_flag);
-                                    flag.store(true, Ordering::Relaxed);
-                                    handle.join().unwrap();
-                                }
-                                let numbers = Arc::clone(&visualizer.numbers);
-                                visualizer.thread =
-                                    Some(thread::spawn(move || algos::shuffle(numbers)));
-                            } else if ui.add(egui::Button::new("Bubble Sort")).clicked() {
-                                if let Some(handle) = visualizer.thread.take() {
-                                    let flag = Arc::clone(&visualizer.stop_flag);
-                                    flag.store(true, Ordering::Relaxed);
-                                    handle.join().unwrap();
-                                }
-                                let (flag, numbers, delay, context) = (
-                                    Arc::clone(&visualizer.stop_flag),
-                                    Arc::clone(&visualizer.numbers),
-                                    Arc::clone(&self.animation_delay_ms),
-                                    ctx.clone(),
-                                );
-                                flag.store(false, Ordering::Relaxed);
-                                visualizer.thread = Some(thread::spawn(move || {
-                                    algos::bubblesort(numbers, delay, &context, flag)
-                                }));
-                            } else if ui.add(egui::Button::new("Quick Sort")).clicked() {
-                                if let Some(handle) = visualizer.thread.take() {
-                                    let flag = Arc::clone(&visualizer.stop_flag);
-                                    flag.store(true, Ordering::Relaxed);
-                                    handle.join().unwrap();
-                                }
-                                let (flag, numbers, highest_index, delay, context) = (
-                                    Arc::clone(&visualizer.stop_flag),
-                                    Arc::clone(&visualizer.numbers),
-                                    Arc::clone(&visualizer.numbers).lock().unwrap().values.len()
-                                        - 1,
-                                    Arc::clone(&self.animation_delay_ms),
-                                    ctx.clone(),
-                                );
-                                flag.store(false, Ordering::Relaxed);
-                                visualizer.thread = Some(thread::spawn(move || {
-                                    algos::quicksort(
-                                        numbers,
-                                        0,
-                                        highest_index,
-                                        &delay,
-                                        &context,
-                                        &flag,
-                                    )
-                                }));
-                            }
-                        });
-                        ui.with_layout(egui::Layout::left_to_right(egui::Align::BOTTOM), |ui| {
-                            let numbers = Arc::clone(&visualizer.numbers);
-                            for num in numbers.lock().unwrap().values.iter() {
-                                let col = match num.highlight {
-                                    datatypes::Highlight::None => num.color,
-                                    datatypes::Highlight::Primary => egui::Color32::KHAKI,
-                                    datatypes::Highlight::Secondary => {
-                                        num.color.linear_multiply(0.5)
+                                    if ui
+                                        .add_enabled(idx > 0, egui::Button::new("<"))
+                                        .on_hover_text("Move panel back")
+                                        .clicked()
+                                    {
+                                        action = Some(PanelAction::MoveBack(idx));
+                                    }
+                                    if ui
+                                        .add_enabled(idx + 1 < count, egui::Button::new(">"))
+                                        .on_hover_text("Move panel forward")
+                                        .clicked()
+                                    {
+                                        action = Some(PanelAction::MoveForward(idx));
                                     }
-                                };
-                                ui.add(&mut VerticalBarWidget::new(num.value, col));
+                                    if ui.add(egui::Button::new("Remove panel")).clicked() {
+                                        action = Some(PanelAction::Remove(idx));
+                                    }
+                                });
+                                visualizer.ui(idx, ui, &panel);
+                            });
+                            if (idx + 1) % self.columns == 0 {
+                                ui.end_row();
                             }
-                        })
-                    },
-                );
-                // Animation speed slider
-                let animation_delay = Arc::clone(&self.animation_delay_ms);
-                let mut speed = delay_to_speed(&animation_delay.load(Ordering::Acquire));
-                ui.add(egui::Slider::new(&mut speed, 1..=10).text("Animation speed"));
-                animation_delay.store(speed_to_delay(&speed), Ordering::Release);
+                        }
+                    });
             });
+            if let Some(action) = action {
+                self.apply_panel_action(action);
+            }
         });
     }
 }
 
 fn main() {
+    if let Some(command) = cli::Cli::parse().command {
+        std::process::exit(cli::run(command));
+    }
+
     let native_options = eframe::NativeOptions {
         default_theme: eframe::Theme::Light,
         initial_window_size: Some(egui::vec2(750., 600.)),
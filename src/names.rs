@@ -0,0 +1,43 @@
+//! Lookup of algorithms, input presets, palettes and waveforms by the names
+//! users type on the command line
+//!
+//! Names match after `normalize`, so "quicksort", "quick-sort" and
+//! "Quick Sort" are all the same.
+
+/// Lowercase `name` and strip everything but letters and digits
+pub fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// The item of `items` whose `name_of` matches `name` after normalizing both
+pub fn find<T: Copy>(items: &[T], name: &str, name_of: impl Fn(&T) -> &'static str) -> Option<T> {
+    let name = normalize(name);
+    items
+        .iter()
+        .find(|item| normalize(name_of(item)) == name)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inputs::InputPreset;
+
+    #[test]
+    fn names_match_ignoring_case_and_punctuation() {
+        for preset in InputPreset::ALL {
+            assert_eq!(
+                find(&InputPreset::ALL, preset.name(), InputPreset::name),
+                Some(preset)
+            );
+        }
+        assert_eq!(
+            find(&InputPreset::ALL, "nearly-sorted", InputPreset::name),
+            Some(InputPreset::NearlySorted)
+        );
+        assert_eq!(find(&InputPreset::ALL, "zigzag", InputPreset::name), None);
+    }
+}
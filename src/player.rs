@@ -0,0 +1,438 @@
+//! Playback of recorded `trace::Trace`s on the `NumberVec`s shown in the UI
+//!
+//! Like the algorithms used to, the player only takes the lock on the
+//! `NumberVec` for as long as it takes to apply a single operation, so the UI
+//! thread is never blocked for long.
+use eframe::egui;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::{thread, time};
+
+use crate::trace::{Counters, Operation, Trace, Tracer};
+use crate::{algos, datatypes};
+
+/// How often a paused player checks whether it should continue
+const PAUSE_POLL_INTERVAL: time::Duration = time::Duration::from_millis(10);
+/// How often a player at a `StartGate` checks whether the others are ready
+const GATE_POLL_INTERVAL: time::Duration = time::Duration::from_millis(1);
+/// Fewest steps between two snapshots of a `Timeline`, longer arrays take
+/// snapshots less often
+const MIN_SNAPSHOT_INTERVAL: usize = 64;
+
+/// Flags to control a running player from another thread
+#[derive(Default)]
+pub struct Controls {
+    /// Set to `true` to abort playback
+    pub stop: AtomicBool,
+    /// While `true`, playback only advances when `step` is set
+    pub pause: AtomicBool,
+    /// Set to `true` to advance a paused player by exactly one step
+    pub step: AtomicBool,
+}
+
+impl Controls {
+    pub fn toggle_pause(&self) {
+        self.pause.fetch_xor(true, Ordering::Relaxed);
+    }
+
+    /// Pause playback and advance by exactly one step
+    pub fn step_once(&self) {
+        self.pause.store(true, Ordering::Relaxed);
+        self.step.store(true, Ordering::Relaxed);
+    }
+
+    /// Block until the next step should be played.
+    ///
+    /// Returns `false` if playback was stopped in the meantime.
+    fn wait_for_next_step(&self, animation_delay: &AtomicU8) -> bool {
+        if !self.pause.load(Ordering::Relaxed) {
+            thread::sleep(time::Duration::from_millis(
+                animation_delay.load(Ordering::Relaxed).into(),
+            ));
+            return !self.stop.load(Ordering::Relaxed);
+        }
+        loop {
+            if self.stop.load(Ordering::Relaxed) {
+                return false;
+            }
+            if self.step.swap(false, Ordering::Relaxed) || !self.pause.load(Ordering::Relaxed) {
+                return true;
+            }
+            thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+    }
+}
+
+/// Lets several players start at the same instant, once all of them are done
+/// recording
+pub struct StartGate {
+    remaining: AtomicUsize,
+    started: OnceLock<time::Instant>,
+}
+
+impl StartGate {
+    pub fn new(players: usize) -> Self {
+        Self {
+            remaining: AtomicUsize::new(players),
+            started: OnceLock::new(),
+        }
+    }
+
+    /// Mark one player as ready and block until all others are, too.
+    ///
+    /// Returns `false` if playback was stopped in the meantime.
+    pub fn wait(&self, controls: &Controls) -> bool {
+        self.remaining.fetch_sub(1, Ordering::AcqRel);
+        while self.remaining.load(Ordering::Acquire) > 0 {
+            if controls.stop.load(Ordering::Relaxed) {
+                return false;
+            }
+            thread::sleep(GATE_POLL_INTERVAL);
+        }
+        self.started.get_or_init(time::Instant::now);
+        true
+    }
+
+    /// When the players were released, if they were already
+    pub fn started(&self) -> Option<time::Instant> {
+        self.started.get().copied()
+    }
+}
+
+/// A recorded run and how far it has been played back
+///
+/// Positions are counted in steps, where a step is an operation like a
+/// comparison or swap (see `Operation::is_step`) together with the highlights
+/// leading up to it. Position 0 is the state
+/// before the first operation.
+#[derive(Clone)]
+pub struct Timeline {
+    /// Algorithm the run was recorded from, if it was recorded by `record`
+    algorithm: Option<&'static dyn algos::SortAlgorithm>,
+    /// State of the numbers and counters every `snapshot_interval` steps,
+    /// starting with the `NumberVec` the trace was recorded from. Only taken
+    /// once the timeline is moved back, up to where it was moved to.
+    snapshots: Vec<(datatypes::NumberVec, Counters)>,
+    /// Steps between two snapshots, at least the number of values, so the
+    /// snapshots take about as much memory as the trace
+    snapshot_interval: usize,
+    trace: Trace,
+    /// Index into `trace.operations` right after each step
+    step_ends: Vec<usize>,
+    position: usize,
+    /// Counted operations up to `position`
+    counters: Counters,
+    /// Counted operations of the whole trace, computed once since the trace
+    /// can be long
+    total_counters: Counters,
+    /// When playback first reached the end
+    finished_at: Option<time::Instant>,
+}
+
+impl Timeline {
+    pub fn new(initial: datatypes::NumberVec, trace: Trace) -> Self {
+        let mut step_ends: Vec<usize> = trace
+            .operations
+            .iter()
+            .enumerate()
+            .filter(|(_, op)| op.is_step())
+            .map(|(i, _)| i + 1)
+            .collect();
+        // trailing markers belong to the last step
+        match step_ends.last_mut() {
+            Some(last) => *last = trace.operations.len(),
+            None if !trace.operations.is_empty() => step_ends.push(trace.operations.len()),
+            None => {}
+        }
+        Self {
+            algorithm: None,
+            snapshot_interval: initial.values.len().max(MIN_SNAPSHOT_INTERVAL),
+            snapshots: vec![(initial, Counters::default())],
+            total_counters: trace.counters(),
+            trace,
+            step_ends,
+            position: 0,
+            counters: Counters::default(),
+            finished_at: None,
+        }
+    }
+
+    /// Record `algorithm` sorting `numbers`, which become the state at
+    /// position 0
+    pub fn record(
+        algorithm: &'static dyn algos::SortAlgorithm,
+        numbers: datatypes::NumberVec,
+    ) -> Self {
+        let mut tracer = Tracer::from_numbers(&numbers);
+        algorithm.run(&mut tracer);
+        Self {
+            algorithm: Some(algorithm),
+            ..Timeline::new(numbers, tracer.finish())
+        }
+    }
+
+    pub fn algorithm(&self) -> Option<&'static dyn algos::SortAlgorithm> {
+        self.algorithm
+    }
+
+    /// State of the numbers at position 0
+    pub fn initial(&self) -> &datatypes::NumberVec {
+        &self.snapshots[0].0
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Total number of steps
+    pub fn len(&self) -> usize {
+        self.step_ends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.step_ends.is_empty()
+    }
+
+    /// All recorded operations, including the markers between steps
+    pub fn operations(&self) -> &[Operation] {
+        &self.trace.operations
+    }
+
+    /// Counters of all operations up to the current position
+    pub fn counters(&self) -> Counters {
+        self.counters
+    }
+
+    /// Counters of the whole run
+    pub fn total_counters(&self) -> Counters {
+        self.total_counters
+    }
+
+    pub fn finished_at(&self) -> Option<time::Instant> {
+        self.finished_at
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.position >= self.len()
+    }
+
+    fn operations_until(&self, step: usize) -> usize {
+        match step {
+            0 => 0,
+            s => self.step_ends[s - 1],
+        }
+    }
+
+    /// The comparison, swap or copy that led to the current position
+    pub fn step_operation(&self) -> Option<&Operation> {
+        let step = self.position.checked_sub(1)?;
+        self.trace.operations[self.operations_until(step)..self.operations_until(self.position)]
+            .iter()
+            .find(|op| op.is_step())
+    }
+
+    /// Apply the next step to `numbers`
+    pub fn step_forward(&mut self, numbers: &mut datatypes::NumberVec) {
+        if !self.at_end() {
+            self.seek(self.position + 1, numbers);
+        }
+    }
+
+    /// Bring `numbers` into the state after `step` steps.
+    ///
+    /// Moving backwards, or forwards past a snapshot, restores the nearest
+    /// snapshot before `step` and replays the trace from there, so highlights
+    /// are exactly as they were at that point.
+    pub fn seek(&mut self, step: usize, numbers: &mut datatypes::NumberVec) {
+        let step = step.min(self.len());
+        let mut snapshot = step / self.snapshot_interval;
+        if step < self.position {
+            self.take_snapshots_until(snapshot);
+        }
+        snapshot = snapshot.min(self.snapshots.len() - 1);
+        let snapshot_step = snapshot * self.snapshot_interval;
+        if step < self.position || snapshot_step > self.position {
+            let (snapshot_numbers, snapshot_counters) = &self.snapshots[snapshot];
+            *numbers = snapshot_numbers.clone();
+            self.counters = *snapshot_counters;
+            self.position = snapshot_step;
+        }
+        let from = self.operations_until(self.position);
+        for op in self.trace.operations[from..self.operations_until(step)].iter() {
+            op.apply(numbers);
+            self.counters.count(op);
+        }
+        self.position = step;
+    }
+
+    /// Replay the trace from the last snapshot until there are snapshots up
+    /// to index `snapshot`
+    fn take_snapshots_until(&mut self, snapshot: usize) {
+        let (mut numbers, mut counters) = self.snapshots[self.snapshots.len() - 1].clone();
+        while self.snapshots.len() <= snapshot {
+            let taken = self.snapshots.len();
+            let from = self.operations_until((taken - 1) * self.snapshot_interval);
+            let until = self.operations_until(taken * self.snapshot_interval);
+            for op in self.trace.operations[from..until].iter() {
+                op.apply(&mut numbers);
+                counters.count(op);
+            }
+            self.snapshots.push((numbers.clone(), counters));
+        }
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Timeline::new(
+            datatypes::NumberVec::new(vec![]),
+            Trace { operations: vec![] },
+        )
+    }
+}
+
+/// Play `timeline` on `numbers` until stopped.
+///
+/// After each step, the player sleeps for `animation_delay` or, while paused,
+/// waits until it's told to advance. Once the end is reached, the player
+/// keeps waiting, since the timeline can be moved back from the UI.
+///
+/// # Arguments
+///
+/// - timeline: recorded run, `numbers` must be in the state of its position
+/// - numbers: `datatypes::NumberVec` to apply the operations to
+/// - animation_delay: time to sleep in ms after each step
+/// - ctx: egui::Context to request repainting after each step
+/// - controls: `Controls` to pause, step or abort from another thread
+/// - on_step: called with the operation of each played step and the
+///   numbers after it, e.g. to make it audible
+pub fn play(
+    timeline: Arc<Mutex<Timeline>>,
+    numbers: Arc<Mutex<datatypes::NumberVec>>,
+    animation_delay: Arc<AtomicU8>,
+    ctx: &egui::Context,
+    controls: Arc<Controls>,
+    mut on_step: impl FnMut(&Operation, &datatypes::NumberVec),
+) {
+    // a step requested before playback started must not skip the first one
+    controls.step.store(false, Ordering::Relaxed);
+    while !controls.stop.load(Ordering::Relaxed) {
+        let mut tl = timeline.lock().unwrap();
+        if tl.at_end() {
+            // covers runs without any steps, others are marked below
+            tl.finished_at.get_or_insert_with(time::Instant::now);
+            drop(tl);
+            // stepping past the end does nothing
+            controls.step.store(false, Ordering::Relaxed);
+            thread::sleep(PAUSE_POLL_INTERVAL);
+            continue;
+        }
+        let mut nums = numbers.lock().unwrap();
+        tl.step_forward(&mut nums);
+        if let Some(op) = tl.step_operation() {
+            on_step(op, &nums);
+        }
+        drop(nums);
+        if tl.at_end() {
+            tl.finished_at.get_or_insert_with(time::Instant::now);
+        }
+        drop(tl);
+
+        ctx.request_repaint();
+        if !controls.wait_for_next_step(&animation_delay) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paused_player_advances_exactly_one_step() {
+        let controls = Controls::default();
+        let delay = AtomicU8::new(0);
+        controls.step_once();
+        assert!(controls.wait_for_next_step(&delay));
+        assert!(!controls.step.load(Ordering::Relaxed));
+        assert!(controls.pause.load(Ordering::Relaxed));
+
+        controls.stop.store(true, Ordering::Relaxed);
+        assert!(!controls.wait_for_next_step(&delay));
+    }
+
+    #[test]
+    fn seeking_back_restores_highlights() {
+        let numbers = datatypes::NumberVec::new(vec![2, 3, 1]);
+        let mut timeline = Timeline::record(&algos::BubbleSort, numbers.clone());
+
+        let mut live = numbers.clone();
+        timeline.seek(2, &mut live);
+        let expected: Vec<(u32, datatypes::Highlights)> = live
+            .values
+            .iter()
+            .map(|n| (n.value, n.highlights))
+            .collect();
+
+        timeline.seek(timeline.len(), &mut live);
+        assert!(live.is_sorted());
+        timeline.seek(2, &mut live);
+        let restored: Vec<(u32, datatypes::Highlights)> = live
+            .values
+            .iter()
+            .map(|n| (n.value, n.highlights))
+            .collect();
+        assert_eq!(restored, expected);
+        assert_eq!(timeline.position(), 2);
+        assert_eq!(timeline.counters().steps, 2);
+        assert!(timeline.step_operation().unwrap().is_step());
+        timeline.seek(0, &mut live);
+        assert!(timeline.step_operation().is_none());
+    }
+
+    #[test]
+    fn seeking_from_snapshots_matches_playing() {
+        let numbers = datatypes::NumberVec::new((1..=40).rev().collect());
+        let mut timeline = Timeline::record(&algos::BubbleSort, numbers.clone());
+        assert!(timeline.len() > 2 * timeline.snapshot_interval);
+
+        let step = timeline.snapshot_interval * 2 + 3;
+        let mut played = numbers.clone();
+        while timeline.position() < step {
+            timeline.step_forward(&mut played);
+        }
+        let counters = timeline.counters();
+        // playing forward never needs a snapshot
+        assert_eq!(timeline.snapshots.len(), 1);
+
+        let mut sought = numbers.clone();
+        timeline.seek(0, &mut sought);
+        timeline.seek(timeline.len(), &mut sought);
+        timeline.seek(step, &mut sought);
+        let state = |n: &datatypes::NumberVec| -> Vec<(u32, datatypes::Highlights, bool)> {
+            n.values
+                .iter()
+                .map(|n| (n.value, n.highlights, n.settled))
+                .collect()
+        };
+        assert_eq!(state(&sought), state(&played));
+        assert_eq!(sought.range, played.range);
+        assert_eq!(timeline.counters(), counters);
+    }
+
+    #[test]
+    fn start_gate_releases_players_once_all_are_ready() {
+        let gate = Arc::new(StartGate::new(2));
+        let waiting = {
+            let gate = Arc::clone(&gate);
+            thread::spawn(move || gate.wait(&Controls::default()))
+        };
+        thread::sleep(time::Duration::from_millis(20));
+        assert!(gate.started().is_none());
+
+        assert!(gate.wait(&Controls::default()));
+        assert!(waiting.join().unwrap());
+        assert!(gate.started().is_some());
+    }
+}
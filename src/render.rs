@@ -0,0 +1,582 @@
+//! Drawing of `NumberVec`s as bar charts
+//!
+//! The geometry and colors of the bars are computed here, so the live view in
+//! the UI and the offline exports look exactly the same. `Canvas` rasterizes
+//! them on the CPU, which works without a screen or GPU. `svg` writes the same
+//! picture as a vector graphic. All colors come from a `Style`.
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+use crate::{datatypes, font};
+
+/// Space between the border of a rendered image and the bars
+const MARGIN: f32 = 4.;
+/// Every font pixel is drawn as a square of this size
+const TEXT_SCALE: u32 = 2;
+/// Vertical space taken by each line of text above the bars
+const LINE_HEIGHT: f32 = ((font::GLYPH_HEIGHT + 3) * TEXT_SCALE) as f32;
+
+/// Opacity of bars outside of the range the algorithm works on
+const OUT_OF_RANGE_OPACITY: f32 = 0.3;
+/// Share of the height taken by the row of the auxiliary buffer, if there is
+/// one
+const BUFFER_SHARE: f32 = 0.3;
+
+/// Colors of the bars, from the first to the last position of the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Palette {
+    /// Red fading to teal
+    Classic,
+    Viridis,
+    Cividis,
+    Grayscale,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Classic,
+        Palette::Viridis,
+        Palette::Cividis,
+        Palette::Grayscale,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Classic => "Classic",
+            Palette::Viridis => "Viridis",
+            Palette::Cividis => "Cividis",
+            Palette::Grayscale => "Grayscale",
+        }
+    }
+
+    /// Whether the shades stay distinguishable with common color vision
+    /// deficiencies, since they differ in lightness and not only in hue
+    pub fn colorblind_safe(&self) -> bool {
+        !matches!(self, Palette::Classic)
+    }
+
+    /// Color of a number with `shade` between 0 and 1
+    pub fn color(&self, shade: f32) -> egui::Color32 {
+        match self {
+            Palette::Classic => {
+                egui::Color32::from_rgb((255. - 255. * shade.clamp(0., 1.)) as u8, 100, 100)
+            }
+            Palette::Viridis => gradient(
+                &[
+                    [68, 1, 84],
+                    [59, 82, 139],
+                    [33, 145, 140],
+                    [94, 201, 98],
+                    [253, 231, 37],
+                ],
+                shade,
+            ),
+            Palette::Cividis => gradient(
+                &[
+                    [0, 32, 77],
+                    [65, 77, 107],
+                    [124, 123, 120],
+                    [188, 175, 111],
+                    [255, 234, 70],
+                ],
+                shade,
+            ),
+            Palette::Grayscale => gradient(&[[220, 220, 220], [40, 40, 40]], shade),
+        }
+    }
+
+    /// Colors of all `datatypes::Highlight::ALL` that stand out from all
+    /// shades, mostly taken from the Okabe-Ito palette
+    pub fn highlights(&self) -> [egui::Color32; datatypes::Highlight::ALL.len()] {
+        let rgb = egui::Color32::from_rgb;
+        match self {
+            Palette::Classic => [
+                egui::Color32::KHAKI,
+                rgb(70, 70, 160),
+                rgb(120, 40, 140),
+                rgb(230, 159, 0),
+                rgb(86, 180, 233),
+                rgb(140, 140, 140),
+                rgb(60, 160, 60),
+            ],
+            Palette::Viridis | Palette::Cividis => [
+                rgb(213, 94, 0),
+                rgb(204, 121, 167),
+                rgb(0, 114, 178),
+                rgb(230, 159, 0),
+                rgb(86, 180, 233),
+                rgb(110, 110, 110),
+                rgb(190, 190, 190),
+            ],
+            Palette::Grayscale => [
+                rgb(230, 159, 0),
+                rgb(0, 114, 178),
+                rgb(86, 180, 233),
+                rgb(213, 94, 0),
+                rgb(240, 228, 66),
+                rgb(204, 121, 167),
+                rgb(0, 158, 115),
+            ],
+        }
+    }
+}
+
+/// Linear interpolation between evenly spaced `stops`
+fn gradient(stops: &[[u8; 3]], shade: f32) -> egui::Color32 {
+    let position = shade.clamp(0., 1.) * (stops.len() - 1) as f32;
+    let idx = (position as usize).min(stops.len() - 2);
+    let t = position - idx as f32;
+    let [r, g, b] = [0, 1, 2]
+        .map(|c| (stops[idx][c] as f32 * (1. - t) + stops[idx + 1][c] as f32 * t).round() as u8);
+    egui::Color32::from_rgb(r, g, b)
+}
+
+/// Everything that determines the colors of a bar chart
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Style {
+    pub palette: Palette,
+    pub dark: bool,
+    /// Color of each highlight, indexed like `datatypes::Highlight::ALL`
+    pub highlights: [egui::Color32; datatypes::Highlight::ALL.len()],
+}
+
+impl Style {
+    /// `palette` with its own highlight colors
+    pub fn new(palette: Palette, dark: bool) -> Self {
+        Self {
+            palette,
+            dark,
+            highlights: palette.highlights(),
+        }
+    }
+
+    pub fn highlight(&self, highlight: datatypes::Highlight) -> egui::Color32 {
+        self.highlights[highlight as usize]
+    }
+
+    pub fn highlight_mut(&mut self, highlight: datatypes::Highlight) -> &mut egui::Color32 {
+        &mut self.highlights[highlight as usize]
+    }
+
+    /// Go back to the palette's own highlight colors
+    pub fn reset_highlights(&mut self) {
+        self.highlights = self.palette.highlights();
+    }
+
+    /// Background of rendered images, the panel color of the egui theme
+    pub fn background(&self) -> egui::Color32 {
+        self.visuals().panel_fill
+    }
+
+    /// Color of text drawn on rendered images
+    pub fn text_color(&self) -> egui::Color32 {
+        if self.dark {
+            egui::Color32::from_gray(200)
+        } else {
+            egui::Color32::from_gray(60)
+        }
+    }
+
+    pub fn visuals(&self) -> egui::Visuals {
+        if self.dark {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        }
+    }
+
+    /// Colors with an explanation of what they mean, for a legend. The first
+    /// entry shows a few shades of the palette.
+    pub fn legend(&self) -> Vec<(Vec<egui::Color32>, &'static str)> {
+        let shades = (0..5).map(|i| self.palette.color(i as f32 / 4.)).collect();
+        let faded = self.palette.color(0.).linear_multiply(OUT_OF_RANGE_OPACITY);
+        let mut legend = vec![(shades, "Position in the input")];
+        legend.extend(
+            datatypes::Highlight::ALL
+                .into_iter()
+                .map(|h| (vec![self.highlight(h)], h.name())),
+        );
+        legend.push((vec![faded], "Outside of the active range"));
+        legend
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style::new(Palette::Classic, false)
+    }
+}
+
+/// Colors of the bar of `num`, taking highlights and the active range into
+/// account. The first one fills the bar, the others mark its top.
+///
+/// Bounds of the active range are marked by position rather than as a
+/// highlight, since highlights move along with swapped values.
+fn bar_colors(
+    num: &datatypes::Number,
+    in_range: bool,
+    bound: bool,
+    style: &Style,
+) -> Vec<egui::Color32> {
+    let mut highlights = num.highlights;
+    if num.settled {
+        highlights.insert(datatypes::Highlight::Sorted);
+    }
+    if bound {
+        highlights.insert(datatypes::Highlight::Bound);
+    }
+    let mut colors: Vec<egui::Color32> = highlights.iter().map(|h| style.highlight(h)).collect();
+    if colors.is_empty() {
+        colors.push(style.palette.color(num.shade));
+    }
+    // fade out everything outside of the active range
+    if in_range {
+        colors
+    } else {
+        colors
+            .into_iter()
+            .map(|col| col.linear_multiply(OUT_OF_RANGE_OPACITY))
+            .collect()
+    }
+}
+
+/// Rectangle of bar `idx` out of `count` bars filling `area`
+fn bar_rect(area: egui::Rect, idx: usize, count: usize, height: f32) -> egui::Rect {
+    let bar_width = area.width() / count.max(1) as f32;
+    // bars only get a gap in between while they are wide enough
+    let gap = if bar_width >= 4. { 1. } else { 0. };
+    let left = area.left() + idx as f32 * bar_width;
+    egui::Rect::from_min_max(
+        egui::pos2(left, area.bottom() - height),
+        egui::pos2(left + bar_width - gap, area.bottom()),
+    )
+}
+
+/// Rectangles and colors of all bars, scaled to fill `area`. Bars with
+/// several highlights get a mark at their top for each one but the first.
+///
+/// The auxiliary buffer, if any, gets a row of its own at the bottom, with
+/// the same scale and a slot below each index of the values.
+pub fn bars(
+    numbers: &datatypes::NumberVec,
+    area: egui::Rect,
+    style: &Style,
+) -> Vec<(egui::Rect, egui::Color32)> {
+    let buffered = numbers.buffer.iter().flatten();
+    let max_value = numbers
+        .values
+        .iter()
+        .chain(buffered)
+        .map(|n| n.value)
+        .max()
+        .unwrap_or(1)
+        .max(1) as f32;
+    let (values_area, buffer_area) = if numbers.buffer.is_empty() {
+        (area, area)
+    } else {
+        let split = area.bottom() - area.height() * BUFFER_SHARE;
+        (
+            egui::Rect::from_min_max(area.min, egui::pos2(area.right(), split - MARGIN)),
+            egui::Rect::from_min_max(egui::pos2(area.left(), split), area.max),
+        )
+    };
+    let in_range = |idx| match numbers.range {
+        Some((lo, hi)) => (lo..=hi).contains(&idx),
+        None => true,
+    };
+    let bound = |idx| matches!(numbers.range, Some((lo, hi)) if idx == lo || idx == hi);
+
+    let mut rects = Vec::with_capacity(numbers.values.len() + numbers.buffer.len());
+    let rows = [
+        (
+            values_area,
+            numbers.values.iter().map(Some).collect::<Vec<_>>(),
+            true,
+        ),
+        (
+            buffer_area,
+            numbers.buffer.iter().map(Option::as_ref).collect(),
+            false,
+        ),
+    ];
+    for (area, row, marks_bounds) in rows {
+        for (i, num) in row.iter().enumerate() {
+            let Some(num) = num else {
+                continue;
+            };
+            let height = area.height() * num.value as f32 / max_value;
+            let bar = bar_rect(area, i, row.len(), height);
+            let colors = bar_colors(num, in_range(i), marks_bounds && bound(i), style);
+            rects.push((bar, colors[0]));
+            let mark_height = bar.width().clamp(2., 8.);
+            for (k, color) in colors.into_iter().enumerate().skip(1) {
+                let top = bar.top() + (k - 1) as f32 * mark_height;
+                let mark = egui::Rect::from_x_y_ranges(bar.x_range(), top..=top + mark_height);
+                rects.push((mark.intersect(bar), color));
+            }
+        }
+    }
+    rects
+}
+
+/// Area of an image of `size` left for the bars below `lines` lines of text
+fn bars_area(size: egui::Vec2, lines: usize) -> egui::Rect {
+    egui::Rect::from_min_max(
+        egui::pos2(MARGIN, MARGIN + lines as f32 * LINE_HEIGHT),
+        egui::pos2(size.x - MARGIN, size.y - MARGIN),
+    )
+}
+
+fn hex_color(color: egui::Color32, background: egui::Color32) -> String {
+    // bars are opaque after blending them over the background
+    let [r, g, b, _] = blend(color, background).to_array();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Blend the premultiplied `color` over the opaque `below`
+fn blend(color: egui::Color32, below: egui::Color32) -> egui::Color32 {
+    let [r, g, b, a] = color.to_array();
+    let keep = 255 - a as u32;
+    let [r, g, b] = [(r, below.r()), (g, below.g()), (b, below.b())]
+        .map(|(top, bottom)| (top as u32 + bottom as u32 * keep / 255).min(255) as u8);
+    egui::Color32::from_rgb(r, g, b)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The bars of `numbers` below `lines` of text as an SVG document
+pub fn svg(
+    numbers: &datatypes::NumberVec,
+    width: u32,
+    height: u32,
+    lines: &[String],
+    style: &Style,
+) -> String {
+    let background = style.background();
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\">\n"
+    );
+    let _ = writeln!(
+        svg,
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        hex_color(background, background)
+    );
+    for (i, line) in lines.iter().enumerate() {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{MARGIN}\" y=\"{}\" font-family=\"monospace\" font-size=\"{}\" fill=\"{}\">{}</text>",
+            MARGIN + (i + 1) as f32 * LINE_HEIGHT - LINE_HEIGHT / 4.,
+            LINE_HEIGHT * 0.8,
+            hex_color(style.text_color(), background),
+            escape_xml(line)
+        );
+    }
+    let size = egui::vec2(width as f32, height as f32);
+    for (rect, color) in bars(numbers, bars_area(size, lines.len()), style) {
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            rect.left(),
+            rect.top(),
+            rect.width(),
+            rect.height(),
+            hex_color(color, background)
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// An RGBA image drawn on the CPU
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    /// Row-major RGBA values without premultiplied alpha
+    pub pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, background: egui::Color32) -> Self {
+        let [r, g, b, _] = background.to_array();
+        Self {
+            width,
+            height,
+            pixels: [r, g, b, 255].repeat((width * height) as usize),
+        }
+    }
+
+    /// Draw all bars of `numbers` on a blank canvas
+    pub fn with_bars(
+        numbers: &datatypes::NumberVec,
+        width: u32,
+        height: u32,
+        style: &Style,
+    ) -> Self {
+        Self::with_bars_and_text(numbers, width, height, &[], style)
+    }
+
+    /// Draw `lines` of text and the bars of `numbers` below them on a blank
+    /// canvas
+    pub fn with_bars_and_text(
+        numbers: &datatypes::NumberVec,
+        width: u32,
+        height: u32,
+        lines: &[String],
+        style: &Style,
+    ) -> Self {
+        let mut canvas = Canvas::new(width, height, style.background());
+        for (i, line) in lines.iter().enumerate() {
+            let top = MARGIN + i as f32 * LINE_HEIGHT;
+            canvas.draw_text(egui::pos2(MARGIN, top), line, style.text_color());
+        }
+        for (rect, color) in bars(numbers, bars_area(canvas.size(), lines.len()), style) {
+            canvas.fill_rect(rect, color);
+        }
+        canvas
+    }
+
+    pub fn size(&self) -> egui::Vec2 {
+        egui::vec2(self.width as f32, self.height as f32)
+    }
+
+    /// Blend `color` over all pixels inside `rect`.
+    ///
+    /// Edges are rounded to whole pixels, but every rectangle covers at least
+    /// one pixel column, so thin bars don't vanish.
+    pub fn fill_rect(&mut self, rect: egui::Rect, color: egui::Color32) {
+        let x0 = (rect.left().round().max(0.) as u32).min(self.width);
+        let x1 = (rect.right().round().max(x0 as f32 + 1.) as u32).min(self.width);
+        let y0 = (rect.top().round().max(0.) as u32).min(self.height);
+        let y1 = (rect.bottom().round().max(0.) as u32).min(self.height);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let idx = ((y * self.width + x) * 4) as usize;
+                let pixel = &mut self.pixels[idx..idx + 3];
+                let below = egui::Color32::from_rgb(pixel[0], pixel[1], pixel[2]);
+                pixel.copy_from_slice(&blend(color, below).to_array()[..3]);
+            }
+        }
+    }
+
+    /// Draw `text` in the built-in pixel font with its top left corner at
+    /// `pos`. Text running past the right edge is cut off.
+    pub fn draw_text(&mut self, pos: egui::Pos2, text: &str, color: egui::Color32) {
+        let pixel = TEXT_SCALE as f32;
+        for (i, c) in text.chars().enumerate() {
+            let left = pos.x + (i as u32 * font::ADVANCE) as f32 * pixel;
+            for (row, bits) in font::glyph(c).into_iter().enumerate() {
+                for col in 0..font::GLYPH_WIDTH {
+                    if bits & (1 << (font::GLYPH_WIDTH - 1 - col)) != 0 {
+                        let min = egui::pos2(left + col as f32 * pixel, pos.y + row as f32 * pixel);
+                        self.fill_rect(
+                            egui::Rect::from_min_size(min, egui::vec2(pixel, pixel)),
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bars_fill_the_area_in_order() {
+        let numbers = datatypes::NumberVec::new(vec![1, 2, 4]);
+        let area = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(30., 40.));
+        let style = Style::default();
+        let bars = bars(&numbers, area, &style);
+        assert_eq!(bars.len(), 3);
+        assert_eq!(bars[0].0.height(), 10.);
+        assert_eq!(bars[2].0.height(), 40.);
+        assert_eq!(bars[2].0.left(), 20.);
+        assert_eq!(bars[0].1, style.palette.color(0.));
+    }
+
+    #[test]
+    fn further_highlights_mark_the_top_of_a_bar() {
+        let mut numbers = datatypes::NumberVec::new(vec![1, 2]);
+        numbers
+            .add_highlight(1, datatypes::Highlight::Bound)
+            .unwrap();
+        numbers
+            .add_highlight(1, datatypes::Highlight::Pivot)
+            .unwrap();
+        let area = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(40., 40.));
+        let style = Style::default();
+        let bars = bars(&numbers, area, &style);
+        assert_eq!(bars.len(), 3);
+        assert_eq!(bars[1].1, style.highlight(datatypes::Highlight::Pivot));
+        assert_eq!(bars[2].1, style.highlight(datatypes::Highlight::Bound));
+        assert_eq!(bars[2].0.top(), bars[1].0.top());
+        assert!(bars[2].0.height() < bars[1].0.height());
+    }
+
+    #[test]
+    fn range_bounds_stay_at_their_positions() {
+        let mut numbers = datatypes::NumberVec::new(vec![1, 2, 3]);
+        numbers.range = Some((1, 2));
+        numbers.values.swap(1, 2);
+        let area = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(30., 30.));
+        let style = Style::default();
+        let bars = bars(&numbers, area, &style);
+        let bound = style.highlight(datatypes::Highlight::Bound);
+        assert_eq!(bars.len(), 3);
+        assert_eq!(bars[1].1, bound);
+        assert_eq!(bars[2].1, bound);
+    }
+
+    #[test]
+    fn buffer_gets_a_row_below_the_values() {
+        let mut numbers = datatypes::NumberVec::new(vec![1, 2]);
+        numbers.buffer = vec![None, Some(numbers.values[0].clone())];
+        let area = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(40., 100.));
+        let bars = bars(&numbers, area, &Style::default());
+        assert_eq!(bars.len(), 3);
+        // the buffered value sits below the second value
+        assert_eq!(bars[2].0.left(), bars[1].0.left());
+        assert!(bars[2].0.top() > bars[1].0.bottom());
+        assert_eq!(bars[2].0.bottom(), 100.);
+    }
+
+    #[test]
+    fn canvas_draws_opaque_bars() {
+        let numbers = datatypes::NumberVec::new(vec![1]);
+        let style = Style::new(Palette::Viridis, true);
+        let canvas = Canvas::with_bars(&numbers, 10, 10, &style);
+        let [r, g, b, _] = style.palette.color(0.).to_array();
+        // center pixel is covered by the only bar, the corner isn't
+        let center = ((5 * 10 + 5) * 4) as usize;
+        assert_eq!(&canvas.pixels[center..center + 3], &[r, g, b]);
+        assert_eq!(&canvas.pixels[0..3], &style.background().to_array()[..3]);
+    }
+
+    #[test]
+    fn gradients_start_and_end_at_their_stops() {
+        assert_eq!(Palette::Grayscale.color(0.), egui::Color32::from_gray(220));
+        assert_eq!(Palette::Grayscale.color(1.), egui::Color32::from_gray(40));
+        assert_eq!(
+            Palette::Viridis.color(0.5),
+            egui::Color32::from_rgb(33, 145, 140)
+        );
+    }
+
+    #[test]
+    fn svg_contains_text_and_one_rect_per_bar() {
+        let numbers = datatypes::NumberVec::new(vec![3, 1, 2]);
+        let svg = svg(&numbers, 60, 40, &["a < b".to_string()], &Style::default());
+        assert!(svg.contains(">a &lt; b</text>"));
+        // one more for the background
+        assert_eq!(svg.matches("<rect").count(), 4);
+    }
+}
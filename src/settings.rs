@@ -0,0 +1,82 @@
+//! Settings remembered between launches
+//!
+//! eframe stores them with its `persistence` feature when the app is closed
+//! and hands them back on the next start. Algorithms are stored by name, so
+//! renamed or removed ones fall back to the first one in `algos::ALGORITHMS`.
+use serde::{Deserialize, Serialize};
+
+use crate::{algos, inputs, render};
+
+const DEFAULT_ARRAY_SIZE: usize = 25;
+
+/// Everything needed to restore the app as it was left
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub animation_delay_ms: u8,
+    pub panels: Vec<PanelSettings>,
+    pub columns: usize,
+    pub array_size: usize,
+    /// Preset applied to all panels at once
+    pub preset: inputs::InputPreset,
+    /// Seed applied to all panels at once
+    pub seed: u64,
+    pub same_input: bool,
+    pub style: render::Style,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            animation_delay_ms: 10,
+            panels: vec![
+                PanelSettings::new(algos::ALGORITHMS[0]),
+                PanelSettings::new(algos::ALGORITHMS[1]),
+            ],
+            columns: 1,
+            array_size: DEFAULT_ARRAY_SIZE,
+            preset: inputs::InputPreset::Random,
+            seed: 0,
+            same_input: true,
+            style: render::Style::default(),
+        }
+    }
+}
+
+/// Settings of a single `AlgoVisualizer`
+#[derive(Serialize, Deserialize)]
+pub struct PanelSettings {
+    /// Name of the algorithm, see `algos::find`
+    pub algorithm: String,
+    pub preset: inputs::InputPreset,
+    pub seed: u64,
+}
+
+impl PanelSettings {
+    /// Settings of a panel showing `algorithm` on the default input
+    pub fn new(algorithm: &'static dyn algos::SortAlgorithm) -> Self {
+        Self {
+            algorithm: algorithm.name().to_string(),
+            preset: inputs::InputPreset::Reversed,
+            seed: 0,
+        }
+    }
+
+    /// The stored algorithm, or the first one if it doesn't exist anymore
+    pub fn algorithm(&self) -> &'static dyn algos::SortAlgorithm {
+        algos::find(&self.algorithm).unwrap_or(algos::ALGORITHMS[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_algorithms_fall_back_to_the_first_one() {
+        let mut panel = PanelSettings::new(algos::ALGORITHMS[1]);
+        assert_eq!(panel.algorithm().name(), algos::ALGORITHMS[1].name());
+        panel.algorithm = "Bogo Sort".to_string();
+        assert_eq!(panel.algorithm().name(), algos::ALGORITHMS[0].name());
+    }
+}
@@ -0,0 +1,161 @@
+//! Keyboard shortcuts for all controls
+//!
+//! Most shortcuts act on the focused panel, their variants with Shift on all
+//! panels. `VisuApp` applies the `Action`s pressed in each frame, the help
+//! overlay lists `BINDINGS`.
+use eframe::egui::{self, Key, KeyboardShortcut, Modifiers};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    NewInput,
+    NewInputForAll,
+    Sort,
+    /// Regenerate the input from its seed and sort it again
+    Restart,
+    Race,
+    TogglePause,
+    TogglePauseForAll,
+    NextStep,
+    NextStepForAll,
+    StepBack,
+    StepBackForAll,
+    SpeedUp,
+    SlowDown,
+    FocusNext,
+    FocusPrevious,
+    /// Focus the panel with this index
+    Focus(usize),
+    MovePanelBack,
+    MovePanelForward,
+    AddPanel,
+    RemovePanel,
+    ExportGif,
+    ExportFrames,
+    /// Cancel the running GIF or frame export
+    CancelExport,
+    SaveSnapshot,
+    ToggleMute,
+    ResetHighlights,
+    ToggleHelp,
+}
+
+pub struct Binding {
+    pub shortcut: KeyboardShortcut,
+    pub action: Action,
+    pub description: &'static str,
+}
+
+const fn binding(
+    modifiers: Modifiers,
+    key: Key,
+    action: Action,
+    description: &'static str,
+) -> Binding {
+    Binding {
+        shortcut: KeyboardShortcut::new(modifiers, key),
+        action,
+        description,
+    }
+}
+
+const fn key(key: Key, action: Action, description: &'static str) -> Binding {
+    binding(Modifiers::NONE, key, action, description)
+}
+
+const fn shift(key: Key, action: Action, description: &'static str) -> Binding {
+    binding(Modifiers::SHIFT, key, action, description)
+}
+
+/// Ctrl, or Cmd on macOS
+const fn command(key: Key, action: Action, description: &'static str) -> Binding {
+    binding(Modifiers::COMMAND, key, action, description)
+}
+
+/// All shortcuts except the number keys, which focus the panel with that
+/// number
+pub const BINDINGS: &[Binding] = &[
+    key(Key::N, Action::NewInput, "New input"),
+    shift(Key::N, Action::NewInputForAll, "New input for all"),
+    key(Key::S, Action::Sort, "Sort"),
+    key(Key::R, Action::Restart, "Restart on the same input"),
+    shift(Key::S, Action::Race, "Race"),
+    key(Key::Space, Action::TogglePause, "Pause or resume"),
+    shift(Key::Space, Action::TogglePauseForAll, "Pause or resume all"),
+    key(Key::ArrowRight, Action::NextStep, "Next step"),
+    shift(Key::ArrowRight, Action::NextStepForAll, "Next step (all)"),
+    key(Key::ArrowLeft, Action::StepBack, "Step back"),
+    shift(Key::ArrowLeft, Action::StepBackForAll, "Step back (all)"),
+    key(Key::ArrowUp, Action::SpeedUp, "Faster animation"),
+    key(Key::ArrowDown, Action::SlowDown, "Slower animation"),
+    key(Key::PageDown, Action::FocusNext, "Focus next panel"),
+    key(Key::PageUp, Action::FocusPrevious, "Focus previous panel"),
+    command(Key::ArrowLeft, Action::MovePanelBack, "Move panel back"),
+    command(
+        Key::ArrowRight,
+        Action::MovePanelForward,
+        "Move panel forward",
+    ),
+    key(Key::A, Action::AddPanel, "Add panel"),
+    key(Key::Delete, Action::RemovePanel, "Remove panel"),
+    key(Key::G, Action::ExportGif, "Export GIF"),
+    key(Key::F, Action::ExportFrames, "Export frames"),
+    key(Key::X, Action::CancelExport, "Cancel export"),
+    key(Key::P, Action::SaveSnapshot, "Save snapshot"),
+    key(Key::M, Action::ToggleMute, "Mute or unmute"),
+    shift(Key::H, Action::ResetHighlights, "Reset highlight colors"),
+    key(Key::H, Action::ToggleHelp, "Show or hide this help"),
+];
+
+const PANEL_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+
+/// Actions whose shortcuts were pressed since the last frame. The key presses
+/// are consumed, so widgets don't react to them as well.
+///
+/// Nothing is pressed while a widget like a text field takes keyboard input.
+pub fn pressed(ctx: &egui::Context) -> Vec<Action> {
+    if ctx.wants_keyboard_input() {
+        return vec![];
+    }
+    let mut input = ctx.input_mut();
+    let mut actions: Vec<Action> = BINDINGS
+        .iter()
+        .filter(|binding| input.consume_shortcut(&binding.shortcut))
+        .map(|binding| binding.action)
+        .collect();
+    for (idx, key) in PANEL_KEYS.into_iter().enumerate() {
+        if input.consume_key(Modifiers::NONE, key) {
+            actions.push(Action::Focus(idx));
+        }
+    }
+    actions
+}
+
+/// Human readable form of `shortcut`, e.g. "Shift+N"
+pub fn format(shortcut: &KeyboardShortcut) -> String {
+    shortcut.format(&egui::ModifierNames::NAMES, cfg!(target_os = "macos"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shortcuts_are_unique() {
+        for (i, binding) in BINDINGS.iter().enumerate() {
+            for other in &BINDINGS[i + 1..] {
+                assert_ne!(binding.shortcut, other.shortcut, "{}", other.description);
+                assert_ne!(binding.action, other.action, "{}", other.description);
+            }
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Sonification of comparisons and swaps
+//!
+//! Every value that is compared or swapped is played as a short tone whose
+//! pitch rises with the value, like in "the sound of sorting". `Mixer`
+//! synthesizes the tones without any audio device, `Output` plays them on the
+//! default one. The latter needs the `audio` feature, since it links against
+//! the system's audio libraries.
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::datatypes;
+use crate::trace::Operation;
+
+/// Pitch of the smallest value
+const MIN_FREQUENCY: f32 = 120.;
+/// Pitch of the largest value
+const MAX_FREQUENCY: f32 = 1200.;
+/// Older tones are cut off once this many play at the same time
+const MAX_TONES: usize = 16;
+/// Length of the tone played for each value during live playback
+pub const TONE_DURATION: Duration = Duration::from_millis(60);
+
+#[derive(Debug)]
+pub enum SoundError {
+    /// The binary was built without the `audio` feature
+    #[cfg(not(feature = "audio"))]
+    Disabled,
+    #[cfg(feature = "audio")]
+    NoDevice,
+    #[cfg(feature = "audio")]
+    Device(String),
+}
+impl std::fmt::Display for SoundError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            #[cfg(not(feature = "audio"))]
+            SoundError::Disabled => write!(f, "Built without sound support"),
+            #[cfg(feature = "audio")]
+            SoundError::NoDevice => write!(f, "No audio device found"),
+            #[cfg(feature = "audio")]
+            SoundError::Device(err) => write!(f, "Could not play sound: {err}"),
+        }
+    }
+}
+impl std::error::Error for SoundError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Triangle,
+    Square,
+    Sawtooth,
+}
+
+impl Waveform {
+    pub const ALL: [Waveform; 4] = [
+        Waveform::Sine,
+        Waveform::Triangle,
+        Waveform::Square,
+        Waveform::Sawtooth,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Waveform::Sine => "Sine",
+            Waveform::Triangle => "Triangle",
+            Waveform::Square => "Square",
+            Waveform::Sawtooth => "Sawtooth",
+        }
+    }
+
+    /// Amplitude between -1 and 1 at `phase`, which is in `0..1`
+    fn sample(&self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (2. * PI * phase).sin(),
+            Waveform::Triangle => 1. - 4. * (phase - 0.5).abs(),
+            Waveform::Square if phase < 0.5 => 1.,
+            Waveform::Square => -1.,
+            Waveform::Sawtooth => 2. * phase - 1.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoundSettings {
+    /// Between 0 (silent) and 1
+    pub volume: f32,
+    pub waveform: Waveform,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            volume: 0.3,
+            waveform: Waveform::Sine,
+        }
+    }
+}
+
+/// Pitch of `value`, rising linearly from the lowest to the highest pitch up
+/// to `max_value`
+pub fn frequency(value: u32, max_value: u32) -> f32 {
+    let ratio = value.min(max_value) as f32 / max_value.max(1) as f32;
+    MIN_FREQUENCY + ratio * (MAX_FREQUENCY - MIN_FREQUENCY)
+}
+
+/// Pitches of the values compared, swapped or copied by `op`, none for other
+/// operations. `numbers` is the state after `op`.
+pub fn step_frequencies(op: &Operation, numbers: &datatypes::NumberVec) -> Vec<f32> {
+    let max_value = numbers.values.iter().map(|n| n.value).max().unwrap_or(1);
+    match *op {
+        Operation::Compare(a, b) | Operation::Swap(a, b) => [a, b]
+            .iter()
+            .filter_map(|&idx| numbers.values.get(idx))
+            .map(|n| frequency(n.value, max_value))
+            .collect(),
+        Operation::CompareBuffer(idx, slot) => [
+            numbers.values.get(idx),
+            numbers.buffer.get(slot).and_then(Option::as_ref),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|n| frequency(n.value, max_value))
+        .collect(),
+        Operation::Copy(_, idx) | Operation::Store(idx, _) | Operation::Load(_, idx) => numbers
+            .values
+            .get(idx)
+            .map(|n| frequency(n.value, max_value))
+            .into_iter()
+            .collect(),
+        _ => vec![],
+    }
+}
+
+struct Tone {
+    frequency: f32,
+    /// Position within the current period, in `0..1`
+    phase: f32,
+    /// Samples played so far
+    elapsed: usize,
+    /// Total length in samples
+    length: usize,
+}
+
+/// Synthesizes all currently playing tones into one mono signal
+pub struct Mixer {
+    sample_rate: u32,
+    pub settings: SoundSettings,
+    tones: Vec<Tone>,
+}
+
+impl Mixer {
+    pub fn new(sample_rate: u32, settings: SoundSettings) -> Self {
+        Self {
+            sample_rate,
+            settings,
+            tones: vec![],
+        }
+    }
+
+    /// Start a tone at `frequency` lasting `duration`
+    pub fn play(&mut self, frequency: f32, duration: Duration) {
+        if self.tones.len() >= MAX_TONES {
+            self.tones.remove(0);
+        }
+        self.tones.push(Tone {
+            frequency,
+            phase: 0.,
+            elapsed: 0,
+            length: (duration.as_secs_f32() * self.sample_rate as f32) as usize,
+        });
+    }
+
+    /// Next sample of all tones mixed together, between -1 and 1
+    pub fn next_sample(&mut self) -> f32 {
+        let mut sum = 0.;
+        for tone in self.tones.iter_mut() {
+            // fade in and out to avoid clicks
+            let envelope = (PI * tone.elapsed as f32 / tone.length.max(1) as f32).sin();
+            sum += self.settings.waveform.sample(tone.phase) * envelope;
+            tone.phase = (tone.phase + tone.frequency / self.sample_rate as f32).fract();
+            tone.elapsed += 1;
+        }
+        self.tones.retain(|tone| tone.elapsed < tone.length);
+        // a comparison plays two tones at full volume
+        (sum * self.settings.volume / 2.).clamp(-1., 1.)
+    }
+}
+
+/// Plays the tones of a shared `Mixer` on the default audio device until
+/// dropped
+pub struct Output {
+    mixer: Arc<Mutex<Mixer>>,
+    #[cfg(feature = "audio")]
+    _stream: cpal::Stream,
+}
+
+impl Output {
+    #[cfg(feature = "audio")]
+    pub fn open(settings: SoundSettings) -> Result<Self, SoundError> {
+        use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or(SoundError::NoDevice)?;
+        let config = device
+            .default_output_config()
+            .map_err(|err| SoundError::Device(err.to_string()))?;
+        if config.sample_format() != cpal::SampleFormat::F32 {
+            return Err(SoundError::Device(format!(
+                "unsupported sample format {}",
+                config.sample_format()
+            )));
+        }
+        let channels = config.channels() as usize;
+        let mixer = Arc::new(Mutex::new(Mixer::new(config.sample_rate().0, settings)));
+        let source = Arc::clone(&mixer);
+        let stream = device
+            .build_output_stream(
+                &config.into(),
+                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                    let mut mixer = source.lock().unwrap();
+                    for frame in data.chunks_mut(channels) {
+                        frame.fill(mixer.next_sample());
+                    }
+                },
+                |err| eprintln!("audio stream failed: {err}"),
+                None,
+            )
+            .map_err(|err| SoundError::Device(err.to_string()))?;
+        stream
+            .play()
+            .map_err(|err| SoundError::Device(err.to_string()))?;
+        Ok(Self {
+            mixer,
+            _stream: stream,
+        })
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn open(_settings: SoundSettings) -> Result<Self, SoundError> {
+        Err(SoundError::Disabled)
+    }
+
+    /// Mixer feeding the device, to start tones from other threads
+    pub fn mixer(&self) -> Arc<Mutex<Mixer>> {
+        Arc::clone(&self.mixer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn larger_values_have_higher_pitch() {
+        assert_eq!(frequency(0, 10), MIN_FREQUENCY);
+        assert_eq!(frequency(10, 10), MAX_FREQUENCY);
+        assert!(frequency(3, 10) < frequency(4, 10));
+    }
+
+    #[test]
+    fn mixer_stays_in_range_and_falls_silent() {
+        let settings = SoundSettings {
+            volume: 1.,
+            waveform: Waveform::Square,
+        };
+        let mut mixer = Mixer::new(1000, settings);
+        for _ in 0..MAX_TONES + 4 {
+            mixer.play(440., Duration::from_millis(10));
+        }
+        for _ in 0..10 {
+            assert!((-1. ..=1.).contains(&mixer.next_sample()));
+        }
+        assert!(mixer.tones.is_empty());
+        assert_eq!(mixer.next_sample(), 0.);
+    }
+}
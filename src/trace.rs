@@ -0,0 +1,304 @@
+//! Recording of algorithm runs as a sequence of operations
+//!
+//! Algorithms don't work on the shared `NumberVec` directly. Instead they run
+//! against a `Tracer`, which owns a plain copy of the values and records every
+//! comparison, swap and highlight as an `Operation`. The resulting
+//! `Trace` can then be applied to a `NumberVec` one operation at a time, which
+//! is what the UI does to animate a run (see `player`).
+use std::cmp::Ordering;
+
+use crate::datatypes;
+
+/// A single event emitted by an algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    /// The values at both indices were compared
+    Compare(usize, usize),
+    /// The values at both indices were swapped
+    Swap(usize, usize),
+    /// The value at the first index was copied over the one at the second
+    Copy(usize, usize),
+    /// The element at the index gets the highlight, in addition to the ones
+    /// it already has
+    Highlight(usize, datatypes::Highlight),
+    /// All highlights are removed
+    ClearHighlights,
+    /// The element at the index is in its final position
+    Settle(usize),
+    /// An empty auxiliary buffer with this many slots is allocated
+    Buffer(usize),
+    /// The value at the index is copied into the buffer slot
+    Store(usize, usize),
+    /// The value in the buffer slot is moved back to the index
+    Load(usize, usize),
+    /// The value at the index was compared with the one in the buffer slot
+    CompareBuffer(usize, usize),
+    /// The algorithm now works on the inclusive range `lo..=hi`
+    Range(usize, usize),
+    /// The algorithm no longer works on a specific range
+    ClearRange,
+    /// The algorithm reached the pseudocode line with this index
+    Line(usize),
+    /// The algorithm is not at any pseudocode line
+    ClearLine,
+}
+
+impl Operation {
+    /// Whether this operation is a step of the algorithm itself, as opposed to
+    /// a purely visual marker like a highlight.
+    pub fn is_step(&self) -> bool {
+        matches!(
+            self,
+            Operation::Compare(..)
+                | Operation::Swap(..)
+                | Operation::Copy(..)
+                | Operation::Store(..)
+                | Operation::Load(..)
+                | Operation::CompareBuffer(..)
+        )
+    }
+
+    pub fn apply(&self, numbers: &mut datatypes::NumberVec) {
+        match *self {
+            Operation::Compare(..) | Operation::CompareBuffer(..) => {}
+            Operation::Swap(i, j) => numbers.values.swap(i, j),
+            Operation::Copy(from, to) => numbers.copy(from, to),
+            Operation::Highlight(idx, highlight) => numbers.add_highlight(idx, highlight).unwrap(),
+            Operation::ClearHighlights => numbers.remove_all_highlights(),
+            Operation::Settle(idx) => numbers.values[idx].settled = true,
+            Operation::Buffer(len) => numbers.buffer = vec![None; len],
+            Operation::Store(idx, slot) => numbers.store(idx, slot),
+            Operation::Load(slot, idx) => numbers.load(slot, idx),
+            Operation::Range(lo, hi) => numbers.range = Some((lo, hi)),
+            Operation::ClearRange => numbers.range = None,
+            Operation::Line(line) => numbers.line = Some(line),
+            Operation::ClearLine => numbers.line = None,
+        }
+    }
+}
+
+/// A complete recording of an algorithm run
+#[derive(Debug, Clone)]
+pub struct Trace {
+    pub operations: Vec<Operation>,
+}
+
+impl Trace {
+    /// Counters for the whole run
+    pub fn counters(&self) -> Counters {
+        let mut counters = Counters::default();
+        for op in self.operations.iter() {
+            counters.count(op);
+        }
+        counters
+    }
+}
+
+/// Number of operations of each kind
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counters {
+    pub comparisons: usize,
+    pub swaps: usize,
+    pub reads: usize,
+    pub writes: usize,
+    /// Comparisons, swaps and copies to and from the buffer, see
+    /// `Operation::is_step`
+    pub steps: usize,
+}
+
+impl Counters {
+    pub fn count(&mut self, op: &Operation) {
+        match op {
+            Operation::Compare(..) | Operation::CompareBuffer(..) => {
+                self.comparisons += 1;
+                self.reads += 2;
+            }
+            Operation::Swap(..) => {
+                self.swaps += 1;
+                self.reads += 2;
+                self.writes += 2;
+            }
+            Operation::Copy(..) | Operation::Store(..) | Operation::Load(..) => {
+                self.reads += 1;
+                self.writes += 1;
+            }
+            _ => {}
+        }
+        if op.is_step() {
+            self.steps += 1;
+        }
+    }
+}
+
+impl std::fmt::Display for Counters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} comparisons, {} swaps, {} reads, {} writes",
+            self.comparisons, self.swaps, self.reads, self.writes
+        )
+    }
+}
+
+/// Records the operations an algorithm performs on its values
+pub struct Tracer {
+    values: Vec<u32>,
+    /// Auxiliary buffer, see `Tracer::allocate_buffer`
+    buffer: Vec<u32>,
+    operations: Vec<Operation>,
+}
+
+impl Tracer {
+    pub fn new(values: Vec<u32>) -> Self {
+        Self {
+            values,
+            buffer: Vec::new(),
+            operations: Vec::new(),
+        }
+    }
+
+    /// Start recording from the current state of `numbers`
+    pub fn from_numbers(numbers: &datatypes::NumberVec) -> Self {
+        Tracer::new(numbers.values.iter().map(|n| n.value).collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Compare the values at `i` and `j`
+    pub fn compare(&mut self, i: usize, j: usize) -> Ordering {
+        self.operations.push(Operation::Compare(i, j));
+        self.values[i].cmp(&self.values[j])
+    }
+
+    pub fn swap(&mut self, i: usize, j: usize) {
+        self.operations.push(Operation::Swap(i, j));
+        self.values.swap(i, j);
+    }
+
+    /// Copy the value at `from` over the one at `to`
+    pub fn copy(&mut self, from: usize, to: usize) {
+        self.operations.push(Operation::Copy(from, to));
+        self.values[to] = self.values[from];
+    }
+
+    /// Allocate an auxiliary buffer with `len` slots, which is shown below the
+    /// values until new input is generated
+    pub fn allocate_buffer(&mut self, len: usize) {
+        self.operations.push(Operation::Buffer(len));
+        self.buffer = vec![0; len];
+    }
+
+    /// Copy the value at `idx` into buffer slot `slot`
+    pub fn store(&mut self, idx: usize, slot: usize) {
+        self.operations.push(Operation::Store(idx, slot));
+        self.buffer[slot] = self.values[idx];
+    }
+
+    /// Copy the value in buffer slot `slot` back to `idx`
+    pub fn load(&mut self, slot: usize, idx: usize) {
+        self.operations.push(Operation::Load(slot, idx));
+        self.values[idx] = self.buffer[slot];
+    }
+
+    /// Compare the value at `idx` with the one in buffer slot `slot`
+    pub fn compare_buffer(&mut self, idx: usize, slot: usize) -> Ordering {
+        self.operations.push(Operation::CompareBuffer(idx, slot));
+        self.values[idx].cmp(&self.buffer[slot])
+    }
+
+    pub fn highlight(&mut self, idx: usize, highlight: datatypes::Highlight) {
+        self.operations.push(Operation::Highlight(idx, highlight));
+    }
+
+    pub fn clear_highlights(&mut self) {
+        self.operations.push(Operation::ClearHighlights);
+    }
+
+    /// Mark the element at `idx` as being in its final position for the rest
+    /// of the run
+    pub fn settle(&mut self, idx: usize) {
+        self.operations.push(Operation::Settle(idx));
+    }
+
+    /// Mark the inclusive range `lo..=hi` as the one currently worked on
+    pub fn range(&mut self, lo: usize, hi: usize) {
+        self.operations.push(Operation::Range(lo, hi));
+    }
+
+    /// Mark the pseudocode line with index `line` as the one executed next
+    pub fn line(&mut self, line: usize) {
+        self.operations.push(Operation::Line(line));
+    }
+
+    /// Stop recording. The trace ends with all markers removed.
+    pub fn finish(mut self) -> Trace {
+        self.clear_highlights();
+        self.operations.push(Operation::ClearRange);
+        self.operations.push(Operation::ClearLine);
+        Trace {
+            operations: self.operations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applying_trace_reproduces_tracer_values() {
+        let mut tracer = Tracer::new(vec![3, 1, 2]);
+        tracer.highlight(0, datatypes::Highlight::Primary);
+        tracer.settle(0);
+        tracer.range(0, 2);
+        tracer.line(1);
+        tracer.swap(0, 1);
+        tracer.swap(1, 2);
+        let trace = tracer.finish();
+
+        let mut numbers = datatypes::NumberVec::new(vec![3, 1, 2]);
+        for op in trace.operations.iter() {
+            op.apply(&mut numbers);
+            // highlights move along with the swapped element
+            if *op == Operation::Swap(1, 2) {
+                assert!(numbers.values[2]
+                    .highlights
+                    .contains(datatypes::Highlight::Primary));
+                assert_eq!(numbers.line, Some(1));
+            }
+        }
+        let values: Vec<u32> = numbers.values.iter().map(|n| n.value).collect();
+        assert_eq!(values, vec![1, 2, 3]);
+        // finishing the trace removes all markers
+        assert_eq!(
+            numbers.values[2].highlights,
+            datatypes::Highlights::default()
+        );
+        // but settled numbers stay settled
+        assert!(numbers.values[2].settled);
+        assert_eq!(numbers.range, None);
+        assert_eq!(numbers.line, None);
+    }
+
+    #[test]
+    fn counters_count_reads_and_writes() {
+        let mut tracer = Tracer::new(vec![2, 1]);
+        tracer.highlight(0, datatypes::Highlight::Primary);
+        if tracer.compare(0, 1) == Ordering::Greater {
+            tracer.swap(0, 1);
+        }
+        let counters = tracer.finish().counters();
+        assert_eq!(
+            counters,
+            Counters {
+                comparisons: 1,
+                swaps: 1,
+                reads: 4,
+                writes: 2,
+                steps: 2,
+            }
+        );
+    }
+}